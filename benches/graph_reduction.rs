@@ -0,0 +1,44 @@
+//! Compares tree-based normal order reduction with graph reduction.
+//! Run with `cargo bench --bench graph_reduction`.
+use cauliflambda::evaluation::{evaluate_graph, evaluate_program, Graph, Program, StepKind};
+use cauliflambda::{parse_program, ParseOptions};
+use std::time::{Duration, Instant};
+
+fn main() {
+    let factorial = ParseOptions::default()
+        .with_numeric_literals()
+        .parse_formula(include_str!("../tests/factorial.lc"))
+        .unwrap();
+    let fibonacci = parse_program(include_str!("../tests/fibonacci.lc")).unwrap();
+    let programs: [(&str, Program); 2] = [
+        ("factorial 4", factorial.value.into()),
+        ("fibonacci 7", fibonacci.value.into()),
+    ];
+
+    println!(
+        "{:<12} {:<24} {:>8} {:>12}",
+        "program", "engine", "steps", "time"
+    );
+    for (name, program) in programs {
+        let (steps, time) = measure(|| evaluate_program(program.clone()).count());
+        print_row(name, "tree", steps, time);
+        let (steps, time) = measure(|| evaluate_graph(program.clone()).count());
+        print_row(name, "graph", steps, time);
+        let (steps, time) = measure(|| {
+            let mut graph = Graph::new(program.clone());
+            (0..).take_while(|_| graph.reduce() != StepKind::Id).count()
+        });
+        print_row(name, "graph (no read back)", steps, time);
+    }
+}
+
+fn measure(f: impl Fn() -> usize) -> (usize, Duration) {
+    const RUNS: u32 = 5;
+    let start = Instant::now();
+    let steps = (0..RUNS).map(|_| f()).last().unwrap();
+    (steps, start.elapsed() / RUNS)
+}
+
+fn print_row(program: &str, engine: &str, steps: usize, time: Duration) {
+    println!("{program:<12} {engine:<24} {steps:>8} {time:>12.2?}");
+}
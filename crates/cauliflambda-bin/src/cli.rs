@@ -0,0 +1,452 @@
+use crate::side_effects::SIDE_EFFECTS;
+use cauliflambda::evaluation::{EvaluationOptions, EvaluationStrategy, ListEncoding};
+use cauliflambda::{FormatOptions, LintOptions, ParseOptions, Sources};
+use std::ffi::OsString;
+use std::fmt;
+use std::io::{self, stdin, Read as _};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+pub const USAGE: &str = "\
+Usage: cauliflambda [COMMAND] [OPTIONS] [FILE]
+
+Commands:
+  run      Evaluate FILE and print its normal form (the default when only FILE is given)
+  trace    Like `run --trace`
+  check    Only parse and check FILE and report diagnostics
+  fmt      Print FILE formatted, or with `--check` only report whether it is
+  repl     Evaluate lines interactively and print every step (the default without arguments)
+
+FILE may be `-` to read from stdin. Only the result is printed to stdout,
+everything else goes to stderr.
+
+Options:
+  --strategy STRATEGY  How to evaluate terms [default: normal-order]
+  --max-steps N        Stop after N steps
+  --no-detect-cycles   Don't stop once a term repeats, which saves a little time every step
+  --format FORMAT      Output as `text`, `json` (JSON Lines) or only the decoded `value`
+  --trace              Print every step, which `json` and the REPL always do
+  --no-prelude         Don't define the booleans, numerals, lists etc. of the prelude
+  --numeric-literals   Read integers as Church numerals, escape names like `42` with backticks
+  --lists ENCODING     Encode `[a, b]` and `\"ab\"` as `church` or `scott` lists [default: church]
+  --strict             Fail on variables that are neither bound nor defined instead of warning
+  --width N            Wrap formatted lines after N characters [default: 80]
+  --check              Fail if FILE isn't formatted instead of printing it (with `fmt`)
+  -q, --quiet          Don't report the decoded value or how the evaluation ended
+  -v, --verbose        Point at the redex of every step in the source
+  -h, --help           Print this help
+
+Exit codes:
+  1  The file could not be read
+  2  The command line is invalid
+  3  The file could not be parsed, uses definitions before they are defined,
+     or has unbound variables (with `--strict`)
+  4  No normal form was found, e.g. because the step limit was hit
+  5  The normal form could not be decoded into a value (with `--format value`)
+  6  The file is not formatted (with `fmt --check`)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Run,
+    Check,
+    Fmt,
+    Repl,
+    Help,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    /// Lines for humans.
+    #[default]
+    Text,
+    /// JSON Lines for other tools, see [`crate::json`].
+    Json,
+    /// Only the value the normal form decodes to.
+    Value,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Verbosity {
+    Quiet,
+    #[default]
+    Normal,
+    Verbose,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    Stdin,
+    File(PathBuf),
+}
+
+impl Input {
+    /// Loads the input together with the files it imports, parsing them with `options`.
+    pub fn load(&self, options: ParseOptions) -> io::Result<Sources> {
+        match self {
+            Input::Stdin => {
+                let mut text = String::new();
+                stdin().read_to_string(&mut text)?;
+                Ok(options.load_text("<stdin>", text, "."))
+            }
+            Input::File(path) => options.load(path).map_err(|error| {
+                io::Error::new(error.kind(), format!("{}: {error}", path.display()))
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Arguments {
+    pub command: Command,
+    pub input: Option<Input>,
+    pub strategy: EvaluationStrategy,
+    pub max_steps: Option<usize>,
+    pub detect_cycles: bool,
+    pub format: Format,
+    pub trace: bool,
+    pub verbosity: Verbosity,
+    pub prelude: bool,
+    pub numeric_literals: bool,
+    pub list_encoding: ListEncoding,
+    pub strict: bool,
+    pub width: Option<usize>,
+    pub check: bool,
+}
+
+impl Arguments {
+    pub fn parse(args: impl IntoIterator<Item = OsString>) -> Result<Self, UsageError> {
+        let mut args = args
+            .into_iter()
+            .map(|arg| arg.to_string_lossy().into_owned());
+        let mut command = None;
+        let mut input = None;
+        let mut arguments = Arguments {
+            command: Command::Repl,
+            input: None,
+            strategy: EvaluationStrategy::default(),
+            max_steps: None,
+            detect_cycles: true,
+            format: Format::default(),
+            trace: false,
+            verbosity: Verbosity::default(),
+            prelude: true,
+            numeric_literals: false,
+            list_encoding: ListEncoding::default(),
+            strict: false,
+            width: None,
+            check: false,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(UsageError::MissingValue(arg.clone()));
+            match arg.as_str() {
+                "--strategy" => {
+                    let strategy = value()?;
+                    arguments.strategy = strategy
+                        .parse()
+                        .map_err(|_| UsageError::InvalidValue("--strategy", strategy))?;
+                }
+                "--max-steps" => {
+                    let max_steps = value()?;
+                    arguments.max_steps = Some(
+                        max_steps
+                            .parse()
+                            .map_err(|_| UsageError::InvalidValue("--max-steps", max_steps))?,
+                    );
+                }
+                "--format" => {
+                    arguments.format = match value()?.as_str() {
+                        "text" => Format::Text,
+                        "json" => Format::Json,
+                        "value" => Format::Value,
+                        format => return Err(UsageError::InvalidValue("--format", format.into())),
+                    }
+                }
+                "--width" => {
+                    let width = value()?;
+                    arguments.width = Some(
+                        width
+                            .parse()
+                            .map_err(|_| UsageError::InvalidValue("--width", width))?,
+                    );
+                }
+                "--lists" => {
+                    let encoding = value()?;
+                    arguments.list_encoding = encoding
+                        .parse()
+                        .map_err(|_| UsageError::InvalidValue("--lists", encoding))?;
+                }
+                "--trace" => arguments.trace = true,
+                "--no-detect-cycles" => arguments.detect_cycles = false,
+                "--no-prelude" => arguments.prelude = false,
+                "--numeric-literals" => arguments.numeric_literals = true,
+                "--strict" => arguments.strict = true,
+                "--check" => arguments.check = true,
+                "-q" | "--quiet" => arguments.verbosity = Verbosity::Quiet,
+                // `--show-redexes` is what `-v` was called before the subcommands
+                "-v" | "--verbose" | "--show-redexes" => arguments.verbosity = Verbosity::Verbose,
+                "-h" | "--help" => command = Some(Command::Help),
+                "-" if input.is_none() => input = Some(Input::Stdin),
+                flag if flag.starts_with('-') && flag != "-" => {
+                    return Err(UsageError::UnknownFlag(arg));
+                }
+                _ if command.is_none() && input.is_none() => {
+                    command = match arg.as_str() {
+                        "run" => Some(Command::Run),
+                        "trace" => {
+                            arguments.trace = true;
+                            Some(Command::Run)
+                        }
+                        "check" => Some(Command::Check),
+                        "fmt" => Some(Command::Fmt),
+                        "repl" => Some(Command::Repl),
+                        "help" => Some(Command::Help),
+                        _ => {
+                            input = Some(Input::File(arg.into()));
+                            None
+                        }
+                    }
+                }
+                _ if input.is_none() => input = Some(Input::File(arg.into())),
+                _ => return Err(UsageError::UnexpectedArgument(arg)),
+            }
+        }
+        arguments.command = match (command, &input) {
+            (Some(Command::Repl), Some(_)) => return Err(UsageError::UnexpectedInput),
+            (Some(command @ (Command::Help | Command::Repl)), _) => command,
+            (Some(_), None) => return Err(UsageError::MissingInput),
+            (Some(command), Some(_)) => command,
+            (None, Some(_)) => Command::Run,
+            (None, None) => Command::Repl,
+        };
+        arguments.input = input;
+        Ok(arguments)
+    }
+
+    pub fn parse_options(&self) -> ParseOptions {
+        let options = ParseOptions::default().with_list_encoding(self.list_encoding);
+        if self.numeric_literals {
+            options.with_numeric_literals()
+        } else {
+            options
+        }
+    }
+
+    /// Adds the prelude (unless it's turned off), the lint options and the strictness of
+    /// the check to sources loaded with [`Arguments::parse_options`].
+    pub fn configure(&self, sources: Sources) -> Sources {
+        let sources =
+            sources.with_lint_options(LintOptions::default().with_side_effects(SIDE_EFFECTS));
+        let sources = if self.strict {
+            sources.with_strict_check()
+        } else {
+            sources
+        };
+        if self.prelude {
+            sources.with_prelude()
+        } else {
+            sources
+        }
+    }
+
+    pub fn format_options(&self) -> FormatOptions {
+        let options = FormatOptions::default().with_parse_options(self.parse_options());
+        match self.width {
+            Some(width) => options.with_width(width),
+            None => options,
+        }
+    }
+
+    pub fn evaluation_options(&self) -> EvaluationOptions {
+        let options = EvaluationOptions::default().with_strategy(self.strategy);
+        let options = if self.detect_cycles {
+            options.with_cycle_detection()
+        } else {
+            options
+        };
+        match self.max_steps {
+            Some(max_steps) => options.with_max_steps(max_steps),
+            None => options,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UsageError {
+    UnknownFlag(String),
+    MissingValue(String),
+    InvalidValue(&'static str, String),
+    UnexpectedArgument(String),
+    MissingInput,
+    UnexpectedInput,
+}
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UsageError::UnknownFlag(flag) => write!(f, "unknown option {flag}"),
+            UsageError::MissingValue(flag) => write!(f, "missing value for {flag}"),
+            UsageError::InvalidValue("--strategy", value) => {
+                let strategies: Vec<_> = EvaluationStrategy::ALL.map(|s| s.to_string()).into();
+                write!(
+                    f,
+                    "unknown strategy {value}, expected one of: {}",
+                    strategies.join(", ")
+                )
+            }
+            UsageError::InvalidValue(flag, value) => write!(f, "invalid value for {flag}: {value}"),
+            UsageError::UnexpectedArgument(arg) => write!(f, "unexpected argument {arg}"),
+            UsageError::MissingInput => f.write_str("missing FILE (or `-` for stdin)"),
+            UsageError::UnexpectedInput => f.write_str("the repl doesn't take a FILE"),
+        }
+    }
+}
+
+/// Why a command failed, which determines the exit code.
+#[derive(Debug)]
+pub enum Failure {
+    Io(io::Error),
+    Usage(UsageError),
+    /// The diagnostics have already been printed.
+    Parse,
+    NoNormalForm,
+    Decode,
+    /// The name of the file that `fmt --check` would change.
+    Unformatted(String),
+}
+
+impl From<io::Error> for Failure {
+    fn from(error: io::Error) -> Self {
+        Failure::Io(error)
+    }
+}
+
+impl From<UsageError> for Failure {
+    fn from(error: UsageError) -> Self {
+        Failure::Usage(error)
+    }
+}
+
+impl From<&Failure> for ExitCode {
+    fn from(failure: &Failure) -> Self {
+        ExitCode::from(match failure {
+            Failure::Io(_) => 1,
+            Failure::Usage(_) => 2,
+            Failure::Parse => 3,
+            Failure::NoNormalForm => 4,
+            Failure::Decode => 5,
+            Failure::Unformatted(_) => 6,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Arguments, UsageError> {
+        Arguments::parse(args.split_whitespace().map(OsString::from))
+    }
+
+    #[test]
+    fn parses_commands() {
+        for (args, command, input) in [
+            ("", Command::Repl, None),
+            ("repl", Command::Repl, None),
+            ("a.lc", Command::Run, Some(Input::File("a.lc".into()))),
+            ("run -", Command::Run, Some(Input::Stdin)),
+            (
+                "check a.lc",
+                Command::Check,
+                Some(Input::File("a.lc".into())),
+            ),
+            ("fmt a.lc", Command::Fmt, Some(Input::File("a.lc".into()))),
+            ("help", Command::Help, None),
+            (
+                "run a.lc --help",
+                Command::Help,
+                Some(Input::File("a.lc".into())),
+            ),
+        ] {
+            let arguments = parse(args).unwrap();
+            assert_eq!(command, arguments.command, "{args}");
+            assert_eq!(input, arguments.input, "{args}");
+        }
+        assert!(parse("trace a.lc").unwrap().trace);
+    }
+
+    #[test]
+    fn parses_flags() {
+        let arguments = parse(
+            "run --strategy call-by-need --max-steps 10 --no-detect-cycles --format json --trace \
+             --no-prelude --numeric-literals --lists scott --strict -q a.lc",
+        )
+        .unwrap();
+        assert_eq!(EvaluationStrategy::CallByNeed, arguments.strategy);
+        assert_eq!(Some(10), arguments.max_steps);
+        assert!(!arguments.detect_cycles);
+        assert_eq!(Format::Json, arguments.format);
+        assert!(arguments.trace && arguments.numeric_literals && arguments.strict);
+        assert!(!arguments.prelude);
+        assert_eq!(ListEncoding::Scott, arguments.list_encoding);
+        assert_eq!(Verbosity::Quiet, arguments.verbosity);
+
+        let arguments = parse("fmt --width 40 --check -v a.lc").unwrap();
+        assert_eq!(Some(40), arguments.width);
+        assert!(arguments.check);
+        assert_eq!(Verbosity::Verbose, arguments.verbosity);
+        assert!(arguments.detect_cycles);
+        let arguments = parse("--show-redexes a.lc").unwrap();
+        assert_eq!(Verbosity::Verbose, arguments.verbosity);
+    }
+
+    #[test]
+    fn rejects_invalid_command_lines() {
+        for (args, error) in [
+            (
+                "--max-steps",
+                UsageError::MissingValue("--max-steps".into()),
+            ),
+            (
+                "--max-steps x a.lc",
+                UsageError::InvalidValue("--max-steps", "x".into()),
+            ),
+            (
+                "--lists foo a.lc",
+                UsageError::InvalidValue("--lists", "foo".into()),
+            ),
+            (
+                "--format xml a.lc",
+                UsageError::InvalidValue("--format", "xml".into()),
+            ),
+            (
+                "--strategy fast a.lc",
+                UsageError::InvalidValue("--strategy", "fast".into()),
+            ),
+            ("--fast a.lc", UsageError::UnknownFlag("--fast".into())),
+            ("- run", UsageError::UnexpectedArgument("run".into())),
+            ("a.lc b.lc", UsageError::UnexpectedArgument("b.lc".into())),
+            ("run", UsageError::MissingInput),
+            ("repl a.lc", UsageError::UnexpectedInput),
+        ] {
+            assert_eq!(Err(error), parse(args).map(|_| ()), "{args}");
+        }
+    }
+
+    #[test]
+    fn maps_failures_to_exit_codes() {
+        for (failure, code) in [
+            (Failure::Io(io::Error::other("")), 1),
+            (Failure::Usage(UsageError::MissingInput), 2),
+            (Failure::Parse, 3),
+            (Failure::NoNormalForm, 4),
+            (Failure::Decode, 5),
+            (Failure::Unformatted("a.lc".into()), 6),
+        ] {
+            assert_eq!(
+                ExitCode::from(code),
+                ExitCode::from(&failure),
+                "{failure:?}"
+            );
+        }
+    }
+}
@@ -1,5 +1,7 @@
+use crate::Style;
+use cauliflambda::evaluation::{Step, StepKind};
 use cauliflambda::{Diagnostic, DiagnosticSeverity};
-use cauliflambda::{Diagnostics, DiagnosticsResult};
+use cauliflambda::{Diagnostics, DiagnosticsResult, Sources};
 use codespan_reporting::diagnostic::Label;
 use codespan_reporting::diagnostic::{Diagnostic as ReportedDiagnostic, Severity};
 use codespan_reporting::files::SimpleFiles;
@@ -7,30 +9,72 @@ use codespan_reporting::term;
 use codespan_reporting::term::termcolor::{ColorChoice, StandardStream};
 
 pub fn unwrap_diagnostics_result<O>(
-    path: &str,
-    input: &str,
+    sources: &Sources,
     result: DiagnosticsResult<O>,
 ) -> Result<O, ()> {
     match result {
         Ok(output) => {
-            print_diagnostics(path, input, &output.diagnostics);
+            print_diagnostics(sources, &output.diagnostics);
             Ok(output.value)
         }
         Err(diagnostics) => {
-            print_diagnostics(path, input, &diagnostics);
+            print_diagnostics(sources, &diagnostics);
             Err(())
         }
     }
 }
 
-pub fn print_diagnostics(file_name: &str, input: &str, diagnostics: &Diagnostics) {
+pub fn print_diagnostics(sources: &Sources, diagnostics: &Diagnostics) {
+    let files = files(sources);
+    for diagnostic in &diagnostics.0 {
+        let diagnostic = to_reported_diagnostic(sources, diagnostic);
+        term::emit(&mut stderr().lock(), &term_config(), &files, &diagnostic).unwrap();
+    }
+}
+
+/// Points at the redex in the source that the `count`th step contracted, if it came from the source.
+pub fn print_redex(sources: &Sources, count: u64, step: &Step) {
+    let Some((file_id, span)) = step.redex.and_then(|span| sources.locate(span)) else {
+        return;
+    };
+    let diagnostic = ReportedDiagnostic::note()
+        .with_message(format!("step {count} ({})", step.kind))
+        .with_labels(vec![
+            Label::primary(file_id, span).with_message(describe_redex(&step.kind))
+        ]);
+    term::emit(
+        &mut stderr().lock(),
+        &term_config(),
+        &files(sources),
+        &diagnostic,
+    )
+    .unwrap();
+}
+
+/// Stderr, colored only if the trace is (see [`Style::for_stderr`]).
+fn stderr() -> StandardStream {
+    StandardStream::stderr(match Style::for_stderr() {
+        Style::Colored => ColorChoice::Always,
+        Style::Plain => ColorChoice::Never,
+    })
+}
+
+/// The files of `sources` with their indices as ids.
+fn files(sources: &Sources) -> SimpleFiles<&str, &str> {
     let mut files = SimpleFiles::new();
-    let file_id = files.add(file_name, input);
+    for source in sources.files() {
+        files.add(source.name.as_str(), source.text.as_str());
+    }
+    files
+}
 
-    for diagnostic in &diagnostics.0 {
-        let diagnostic = to_reported_diagnostic(file_id, diagnostic);
-        let writer = StandardStream::stderr(ColorChoice::Always);
-        term::emit(&mut writer.lock(), &term_config(), &files, &diagnostic).unwrap();
+fn describe_redex(kind: &StepKind) -> &'static str {
+    match kind {
+        StepKind::Alpha => "renamed the bound variables of this redex",
+        StepKind::Beta => "contracted this redex",
+        StepKind::Delta => "expanded this definition",
+        StepKind::SideEffect => "performed this side effect",
+        _ => "reduced this term",
     }
 }
 
@@ -42,19 +86,25 @@ fn term_config() -> term::Config {
     }
 }
 
-fn to_reported_diagnostic(file_id: usize, diagnostic: &Diagnostic) -> ReportedDiagnostic<usize> {
+fn to_reported_diagnostic(sources: &Sources, diagnostic: &Diagnostic) -> ReportedDiagnostic<usize> {
     ReportedDiagnostic::new(to_severity(diagnostic.severity))
         .with_message(diagnostic.message.as_ref())
         .with_labels(
             diagnostic
                 .labels
                 .iter()
-                .map(|label| {
-                    Label::primary(file_id, label.location.clone())
-                        .with_message(label.message.to_owned().unwrap_or_default())
+                .enumerate()
+                .filter_map(|(index, label)| {
+                    let (file_id, span) = sources.locate(label.location)?;
+                    let reported = match index {
+                        0 => Label::primary(file_id, span),
+                        _ => Label::secondary(file_id, span),
+                    };
+                    Some(reported.with_message(label.message.to_owned().unwrap_or_default()))
                 })
                 .collect(),
         )
+        .with_notes(diagnostic.notes.clone())
 }
 
 fn to_severity(severity: DiagnosticSeverity) -> Severity {
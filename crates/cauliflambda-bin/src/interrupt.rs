@@ -0,0 +1,38 @@
+use cauliflambda::evaluation::CancellationToken;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+static INTERRUPTED: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+/// Returns a token that is cancelled by Ctrl-C instead of terminating the process.
+/// Tokens returned earlier are reset.
+pub fn cancel_on_interrupt() -> CancellationToken {
+    let interrupted = INTERRUPTED.get_or_init(|| {
+        install_handler();
+        Arc::default()
+    });
+    interrupted.store(false, Ordering::Relaxed);
+    interrupted.clone().into()
+}
+
+#[cfg(unix)]
+fn install_handler() {
+    use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+
+    extern "C" fn on_interrupt(_: nix::libc::c_int) {
+        if let Some(interrupted) = INTERRUPTED.get() {
+            interrupted.store(true, Ordering::Relaxed);
+        }
+    }
+
+    let action = SigAction::new(
+        SigHandler::Handler(on_interrupt),
+        SaFlags::SA_RESTART,
+        SigSet::empty(),
+    );
+    // SAFETY: the handler only performs an atomic store.
+    unsafe { sigaction(Signal::SIGINT, &action) }.expect("failed to install Ctrl-C handler");
+}
+
+#[cfg(not(unix))]
+fn install_handler() {}
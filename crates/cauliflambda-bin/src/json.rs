@@ -0,0 +1,343 @@
+//! Traces as [JSON Lines](https://jsonlines.org/) for other tools to consume.
+//!
+//! With `--format json` every step is written as an object like
+//! `{"type":"step","step":1,"kind":"beta","term":"A","ast":{...},"redex":{...},"size":1}`
+//! and the trace ends with a `{"type":"summary",...}` object. The redex of a step is located
+//! in the source like `{"file":"main.lc","line":1,"column":3}` (counting from 1), if it's from
+//! the source.
+//! What side effects like `:write` print is written as `{"type":"output","text":"42\n"}`.
+use cauliflambda::evaluation::{
+    Decode as _, Direction, Disambiguator, Outcome, Path, Step, StepKind, Term, Value, Variable,
+};
+use cauliflambda::syntax::Span;
+use cauliflambda::Sources;
+use std::collections::BTreeMap;
+use std::fmt::{self, Write as _};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(u64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Json::Bool(value)
+    }
+}
+
+impl From<u64> for Json {
+    fn from(value: u64) -> Self {
+        Json::Number(value)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(value: usize) -> Self {
+        Json::Number(value as u64)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Self {
+        Json::String(value.to_owned())
+    }
+}
+
+impl From<String> for Json {
+    fn from(value: String) -> Self {
+        Json::String(value)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Json::Null, Into::into)
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(value) => write!(f, "{value}"),
+            Json::Number(value) => write!(f, "{value}"),
+            Json::String(value) => write_string(value, f),
+            Json::Array(values) => {
+                f.write_char('[')?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(entries) => {
+                f.write_char('{')?;
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    if index > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(key, f)?;
+                    write!(f, ":{value}")?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn write_string(value: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_char('"')?;
+    for c in value.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+/// The `count`th step of an evaluation of `sources`.
+pub fn step(count: u64, step: &Step, sources: &Sources) -> Json {
+    Json::Object(vec![
+        ("type", "step".into()),
+        ("step", count.into()),
+        ("kind", kind_name(&step.kind).into()),
+        ("term", step.term.to_string().into()),
+        ("ast", term(&step.term)),
+        (
+            "redex",
+            Json::Object(vec![
+                (
+                    "location",
+                    step.redex.and_then(|span| location(sources, span)).into(),
+                ),
+                ("path", step.redex_path.as_ref().map(path).into()),
+            ]),
+        ),
+        (
+            "contractum",
+            Json::Object(vec![(
+                "path",
+                step.contractum_path.as_ref().map(path).into(),
+            )]),
+        ),
+        ("size", step.term.size().into()),
+    ])
+}
+
+/// The end of an evaluation, with the decoded value of its last term if it has one.
+pub fn summary(
+    normal_form: &Term,
+    outcome: Option<Outcome>,
+    steps: u64,
+    kinds: &BTreeMap<&'static str, u64>,
+) -> Json {
+    let kinds = kinds
+        .iter()
+        .map(|(kind, count)| (*kind, (*count).into()))
+        .collect();
+    Json::Object(vec![
+        ("type", "summary".into()),
+        ("outcome", outcome.map(self::outcome).into()),
+        ("term", normal_form.to_string().into()),
+        ("value", Value::decode(normal_form).map(value).into()),
+        ("steps", steps.into()),
+        ("kinds", Json::Object(kinds)),
+    ])
+}
+
+/// The text that a side effect printed.
+pub fn output(text: &str) -> Json {
+    Json::Object(vec![("type", "output".into()), ("text", text.into())])
+}
+
+pub fn kind_name(kind: &StepKind) -> &'static str {
+    match kind {
+        StepKind::Id => "id",
+        StepKind::Alpha => "alpha",
+        StepKind::Beta => "beta",
+        StepKind::Delta => "delta",
+        StepKind::SideEffect => "side-effect",
+        StepKind::Deref => "deref",
+        StepKind::Lift => "lift",
+        StepKind::Assoc => "assoc",
+    }
+}
+
+/// The structure of a term, e.g. `{"type":"app","left":{...},"right":{...}}`.
+pub fn term(term: &Term) -> Json {
+    match term {
+        Term::Var(variable) => self::variable(variable),
+        Term::Abs(abs) => Json::Object(vec![
+            ("type", "abs".into()),
+            ("variable", self::variable(&abs.variable)),
+            ("body", self::term(&abs.term)),
+        ]),
+        Term::App(app) => Json::Object(vec![
+            ("type", "app".into()),
+            ("left", self::term(&app.left)),
+            ("right", self::term(&app.right)),
+        ]),
+    }
+}
+
+fn variable(variable: &Variable) -> Json {
+    let mut entries = vec![("type", "var".into()), ("name", variable.name.into())];
+    match variable.disambiguator {
+        Disambiguator::Symbol => entries.push(("symbol", true.into())),
+        Disambiguator::Numeric(index) => entries.push(("index", index.into())),
+        Disambiguator::None => {}
+    }
+    Json::Object(entries)
+}
+
+/// The file, line and column (in characters) where `span` starts.
+fn location(sources: &Sources, span: Span) -> Option<Json> {
+    let (index, span) = sources.locate(span)?;
+    let file = &sources.files()[index];
+    let before = &file.text[..span.start];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .count()
+        + 1;
+    Some(Json::Object(vec![
+        ("file", file.name.as_str().into()),
+        ("line", line.into()),
+        ("column", column.into()),
+    ]))
+}
+
+fn path(path: &Path) -> Json {
+    Json::Array(
+        path.directions()
+            .iter()
+            .map(|direction| match direction {
+                Direction::Body => "body".into(),
+                Direction::Left => "left".into(),
+                Direction::Right => "right".into(),
+            })
+            .collect(),
+    )
+}
+
+fn outcome(outcome: Outcome) -> Json {
+    let name = match outcome {
+        Outcome::NormalForm => "normal-form",
+        Outcome::StepLimitReached => "step-limit-reached",
+        Outcome::SizeLimitExceeded => "size-limit-exceeded",
+        Outcome::DeadlineExceeded => "deadline-exceeded",
+        Outcome::Cancelled => "cancelled",
+        Outcome::Diverges {
+            cycle_start,
+            cycle_length,
+        } => {
+            return Json::Object(vec![
+                ("type", "diverges".into()),
+                ("cycle_start", cycle_start.into()),
+                ("cycle_length", cycle_length.into()),
+            ])
+        }
+        _ => "unknown",
+    };
+    Json::Object(vec![("type", name.into())])
+}
+
+fn value(value: Value) -> Json {
+    match value {
+        Value::Integer(value) => {
+            Json::Object(vec![("type", "integer".into()), ("value", value.into())])
+        }
+        Value::Bool(value) => Json::Object(vec![("type", "bool".into()), ("value", value.into())]),
+        Value::String(value) => {
+            Json::Object(vec![("type", "string".into()), ("value", value.into())])
+        }
+        Value::List(items) => Json::Object(vec![
+            ("type", "list".into()),
+            (
+                "items",
+                Json::Array(items.into_iter().map(self::value).collect()),
+            ),
+        ]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cauliflambda::evaluation::{abs, app};
+
+    #[test]
+    fn writes_values() {
+        let json = Json::Object(vec![
+            ("null", Json::Null),
+            ("bool", true.into()),
+            ("number", 42_u64.into()),
+            ("array", Json::Array(vec![1_u64.into(), "a".into()])),
+            ("empty", Json::Array(vec![])),
+            ("object", Json::Object(vec![])),
+        ]);
+        assert_eq!(
+            r#"{"null":null,"bool":true,"number":42,"array":[1,"a"],"empty":[],"object":{}}"#,
+            json.to_string()
+        );
+    }
+
+    #[test]
+    fn escapes_strings() {
+        let json = Json::from("\"quoted\" \\ λ\n\r\t\x07\u{7f}");
+        assert_eq!(r#""\"quoted\" \\ λ\n\r\t\u0007\u007f""#, json.to_string());
+    }
+
+    #[test]
+    fn writes_output_of_side_effects() {
+        assert_eq!(
+            r#"{"type":"output","text":"42\n"}"#,
+            output("42\n").to_string()
+        );
+    }
+
+    #[test]
+    fn locates_spans_by_line_and_character() {
+        let sources = Sources::load_text("main.lc", "A -> (λx.x)\nλy.A y", ".");
+        let span = Span { start: 17, end: 18 };
+        assert_eq!(
+            r#"{"file":"main.lc","line":2,"column":4}"#,
+            location(&sources, span).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn writes_structure_of_terms() {
+        let term = abs(
+            "x",
+            app(
+                Variable::new_with("y", 1),
+                Variable::new_with("s", Disambiguator::Symbol),
+            ),
+        );
+        assert_eq!(
+            concat!(
+                r#"{"type":"abs","variable":{"type":"var","name":"x"},"body":{"type":"app","#,
+                r#""left":{"type":"var","name":"y","index":1},"#,
+                r#""right":{"type":"var","name":"s","symbol":true}}}"#
+            ),
+            self::term(&term).to_string()
+        );
+    }
+}
@@ -1,50 +1,221 @@
-use cauliflambda::evaluation::{evaluate_with_side_effects, Decode as _, Step, Term, Value};
-use cauliflambda::parse_program;
-use diagnostics::unwrap_diagnostics_result;
+use cauliflambda::evaluation::{
+    Decode as _, EvaluationOptions, EvaluationStrategy, Outcome, Path as TermPath, Term, Value,
+};
+use cauliflambda::syntax::Program;
+use cauliflambda::Sources;
+use cli::{Arguments, Command, Failure, Format, Verbosity, USAGE};
+use diagnostics::{print_redex, unwrap_diagnostics_result};
 use repl::repl;
+use side_effects::SideEffects;
+use std::collections::BTreeMap;
 use std::env;
-use std::error::Error;
-use std::fs::read_to_string;
-use std::path::Path;
-use std::process::exit;
+use std::io::{self, stderr, IsTerminal};
+use std::process::ExitCode;
 
+mod cli;
 mod diagnostics;
+mod interrupt;
+mod json;
 mod repl;
 mod side_effects;
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let args = env::args_os().skip(1).collect::<Vec<_>>();
-    match args.len() {
-        0 => repl(),
-        1 => evaluate_file(Path::new(&args[0])),
-        _ => help(),
+fn main() -> ExitCode {
+    match Arguments::parse(env::args_os().skip(1))
+        .map_err(Failure::from)
+        .and_then(|arguments| run(&arguments))
+    {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            report(&failure);
+            ExitCode::from(&failure)
+        }
     }
 }
 
-fn help() -> Result<(), Box<dyn Error>> {
-    println!("Usage: {} [FILE]", env::args().next().unwrap());
-    Ok(())
+/// Explains a failure on stderr, unless that happened already (e.g. for parse errors).
+fn report(failure: &Failure) {
+    match failure {
+        Failure::Io(error) => eprintln!("error: {error}"),
+        Failure::Usage(error) => eprintln!("error: {error}\n\n{USAGE}"),
+        Failure::Parse | Failure::NoNormalForm => {}
+        Failure::Decode => eprintln!("error: the normal form is not a value"),
+        Failure::Unformatted(name) => eprintln!("error: {name} is not formatted"),
+    }
+}
+
+fn run(arguments: &Arguments) -> Result<(), Failure> {
+    match arguments.command {
+        Command::Help => {
+            let strategies: Vec<_> = EvaluationStrategy::ALL.map(|s| s.to_string()).into();
+            println!("{USAGE}\n\nStrategies: {}", strategies.join(", "));
+            Ok(())
+        }
+        Command::Repl => {
+            repl(arguments).map_err(|error| Failure::Io(io::Error::other(error.to_string())))
+        }
+        Command::Check => with_input(arguments, |_, _| Ok(())),
+        Command::Fmt => format(arguments),
+        Command::Run => with_input(arguments, |sources, program| {
+            evaluate(arguments, arguments.evaluation_options(), sources, program)
+        }),
+    }
 }
 
-// TODO: print normal form to stdout, everything else to stderr
-fn evaluate_file(path: &Path) -> Result<(), Box<dyn Error>> {
-    let input = read_to_string(path)?;
-    let program = unwrap_diagnostics_result(&path.to_string_lossy(), &input, parse_program(&input))
-        .unwrap_or_else(|_| exit(1));
+/// Prints the input formatted, or with `--check` fails if it isn't formatted already.
+/// Only the input is formatted, not the files it imports.
+fn format(arguments: &Arguments) -> Result<(), Failure> {
+    let input = arguments
+        .input
+        .as_ref()
+        .ok_or(cli::UsageError::MissingInput)?;
+    let sources = input.load(arguments.parse_options())?;
+    let file = &sources.files()[0];
+    let formatted = arguments.format_options().format(&file.text);
+    let formatted = unwrap_diagnostics_result(&sources, formatted).map_err(|()| Failure::Parse)?;
+    if !arguments.check {
+        print!("{formatted}");
+    } else if formatted != file.text {
+        return Err(Failure::Unformatted(file.name.clone()));
+    }
+    Ok(())
+}
 
-    println!("{}", program.formula);
+/// Loads and parses the input, reporting diagnostics before passing on the program.
+fn with_input(
+    arguments: &Arguments,
+    f: impl FnOnce(&Sources, Program) -> Result<(), Failure>,
+) -> Result<(), Failure> {
+    let input = arguments
+        .input
+        .as_ref()
+        .ok_or(cli::UsageError::MissingInput)?;
+    let sources = arguments.configure(input.load(arguments.parse_options())?);
+    let program =
+        unwrap_diagnostics_result(&sources, sources.program()).map_err(|()| Failure::Parse)?;
+    f(&sources, program)
+}
 
-    let mut count = 0;
-    let mut normal_form: Term = program.formula.clone().into();
-    for Step { term, kind, .. } in evaluate_with_side_effects(program.formula) {
+/// Prints the result of the evaluation to stdout and everything else to stderr,
+/// so that the output can be piped into other programs.
+fn evaluate(
+    arguments: &Arguments,
+    options: EvaluationOptions,
+    sources: &Sources,
+    program: Program,
+) -> Result<(), Failure> {
+    let style = Style::for_stderr();
+    let mut count: u64 = 0;
+    let mut kinds = BTreeMap::new();
+    let mut line = Line {
+        prefix: String::new(),
+        term: program.formula.clone().into(),
+        contractum: None,
+    };
+    let mut evaluation = options.evaluate_with_side_effects(program, SideEffects(arguments.format));
+    for step in evaluation.by_ref() {
         count += 1;
-        normal_form = term.clone();
-        println!("->>{kind} {term}");
+        *kinds.entry(json::kind_name(&step.kind)).or_default() += 1;
+        match arguments.format {
+            Format::Json => println!("{}", json::step(count, &step, sources)),
+            _ if arguments.trace => line.print(style, step.redex_path.as_ref()),
+            _ => {}
+        }
+        if arguments.verbosity == Verbosity::Verbose {
+            print_redex(sources, count, &step);
+        }
+        line = Line {
+            prefix: format!("->>{} ", step.kind),
+            term: step.term,
+            contractum: step.contractum_path,
+        };
+    }
+    let outcome = evaluation.outcome();
+    if arguments.trace && arguments.format != Format::Json {
+        line.print(style, None);
+    }
+    let term = &line.term;
+    let normal_form = outcome == Some(Outcome::NormalForm);
+    match arguments.format {
+        Format::Text => {
+            // The trace in the REPL already ends with the normal form
+            if normal_form && !(arguments.trace && arguments.command == Command::Repl) {
+                println!("{term}");
+            } else if !arguments.trace {
+                eprintln!("{term}");
+            }
+            if arguments.verbosity != Verbosity::Quiet {
+                if let Some(value) = Value::decode(term).filter(|_| normal_form) {
+                    eprintln!("~~> {value}");
+                }
+                eprintln!("{}", describe_outcome(outcome, count));
+            }
+        }
+        Format::Json => println!("{}", json::summary(term, outcome, count, &kinds)),
+        Format::Value if normal_form => {
+            println!("{}", Value::decode(term).ok_or(Failure::Decode)?)
+        }
+        Format::Value => {}
+    }
+    match outcome {
+        Some(Outcome::NormalForm) => Ok(()),
+        _ => Err(Failure::NoNormalForm),
     }
-    if let Some(value) = Value::decode(&normal_form) {
-        println!("~~> {value}");
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Plain,
+    /// Underlines redexes and colors contracta with ANSI escape codes.
+    Colored,
+}
+
+impl Style {
+    fn for_stderr() -> Self {
+        if stderr().is_terminal() && env::var_os("NO_COLOR").is_none() {
+            Style::Colored
+        } else {
+            Style::Plain
+        }
     }
-    println!("Found normal form after {count} steps");
+}
 
-    Ok(())
+/// A line of the evaluation, which is printed once the redex of the next step is known.
+struct Line<'a> {
+    prefix: String,
+    term: Term<'a>,
+    contractum: Option<TermPath>,
+}
+
+impl Line<'_> {
+    fn print(&self, style: Style, redex: Option<&TermPath>) {
+        let mut highlighted = self.term.highlighted();
+        if style == Style::Colored {
+            if let Some(contractum) = &self.contractum {
+                highlighted = highlighted.with_highlight(contractum, "\x1b[32m", "\x1b[39m");
+            }
+            if let Some(redex) = redex {
+                highlighted = highlighted.with_highlight(redex, "\x1b[4m", "\x1b[24m");
+            }
+        }
+        eprintln!("{}{highlighted}", self.prefix);
+    }
+}
+
+fn describe_outcome(outcome: Option<Outcome>, count: u64) -> String {
+    let steps = match count {
+        1 => "1 step".to_string(),
+        _ => format!("{count} steps"),
+    };
+    match outcome {
+        Some(Outcome::NormalForm) => format!("Found normal form after {steps}"),
+        Some(Outcome::StepLimitReached) => format!("Reached step limit after {steps}"),
+        Some(Outcome::SizeLimitExceeded) => format!("Term grew too large after {steps}"),
+        Some(Outcome::DeadlineExceeded) => format!("Timed out after {steps}"),
+        Some(Outcome::Cancelled) => format!("Cancelled after {steps}"),
+        Some(Outcome::Diverges { cycle_start, .. }) => {
+            format!("Term loops after {steps} (it repeats the term after step {cycle_start})")
+        }
+        Some(_) => format!("Stopped after {steps}"),
+        None => unreachable!("the evaluation is only described once it has stopped"),
+    }
 }
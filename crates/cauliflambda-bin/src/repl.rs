@@ -1,12 +1,20 @@
+use crate::cli::Arguments;
 use crate::diagnostics::unwrap_diagnostics_result;
-use cauliflambda::evaluation::{evaluate_with_side_effects, Decode, Step, Term, Value};
-use cauliflambda::parse_program;
+use crate::interrupt::cancel_on_interrupt;
+use crate::{evaluate, report};
+use cauliflambda::evaluation::EvaluationOptions;
 use rustyline::error::ReadlineError;
 use rustyline::validate::MatchingBracketValidator;
 use rustyline::{Completer, Editor, Helper, Highlighter, Hinter, Validator};
 use std::error::Error;
 
-pub fn repl() -> Result<(), Box<dyn Error>> {
+/// Evaluates lines like `run --trace`, there's no output to pipe in the REPL.
+pub fn repl(arguments: &Arguments) -> Result<(), Box<dyn Error>> {
+    let arguments = &Arguments {
+        trace: true,
+        ..arguments.clone()
+    };
+    let options = arguments.evaluation_options();
     let mut rl: Editor<ReplHelper, _> = Editor::new()?;
     rl.set_helper(Some(ReplHelper::new()));
 
@@ -14,7 +22,7 @@ pub fn repl() -> Result<(), Box<dyn Error>> {
         match rl.readline(">> ") {
             Ok(input) => {
                 rl.add_history_entry(&input)?;
-                process_line(&input);
+                process_line(&input, arguments, &options);
             }
             Err(ReadlineError::Interrupted | ReadlineError::Eof) => {
                 println!("Goodbye ✨");
@@ -44,18 +52,13 @@ impl ReplHelper {
     }
 }
 
-fn process_line(input: &str) {
-    if let Ok(program) = unwrap_diagnostics_result("<stdin>", input, parse_program(input)) {
-        let mut count: u64 = 0;
-        let mut normal_form: Term = program.formula.clone().into();
-        for Step { term, kind, .. } in evaluate_with_side_effects(program.formula) {
-            count += 1;
-            normal_form = term.clone();
-            println!("->>{kind} {term}");
+/// Evaluates a line like `run` evaluates a file.
+fn process_line(input: &str, arguments: &Arguments, options: &EvaluationOptions) {
+    let sources = arguments.configure(arguments.parse_options().load_text("<stdin>", input, "."));
+    if let Ok(program) = unwrap_diagnostics_result(&sources, sources.program()) {
+        let options = options.clone().with_cancellation(cancel_on_interrupt());
+        if let Err(failure) = evaluate(arguments, options, &sources, program) {
+            report(&failure);
         }
-        if let Some(value) = Value::decode(&normal_form) {
-            println!("~~> {value}");
-        }
-        println!("Found normal form after {count} steps");
     }
 }
@@ -1,27 +1,42 @@
-use cauliflambda::evaluation::{abs, app, Encode as _, Term, Variable};
+use crate::cli::Format;
+use crate::json;
+use cauliflambda::evaluation::{abs, app, EffectHandler, Encode as _, Term, Variable};
 use rand::{thread_rng, Rng};
 use std::io::stdin;
 
-pub(crate) fn perform_side_effect<'a>(s: &'a str, term: Term<'a>) -> Option<Term<'a>> {
-    const F: Variable<'_> = Variable::new("f");
+/// The symbols that [`SideEffects`] understands.
+pub(crate) const SIDE_EFFECTS: &[&str] = &["beep", "rand", "read", "write"];
 
-    match s {
-        "beep" => {
-            beep();
-            Some(term)
+/// Performs the side effects, whose output is written in the given format.
+pub(crate) struct SideEffects(pub Format);
+
+impl<'a> EffectHandler<'a> for SideEffects {
+    fn perform(&mut self, s: &'a str, term: &Term<'a>) -> Option<Term<'a>> {
+        const F: Variable<'_> = Variable::new("f");
+        let SideEffects(format) = *self;
+
+        match s {
+            "beep" => {
+                output(format, "\x07");
+                Some(term.clone())
+            }
+            "rand" => Some(abs(F, app(F, rand(term).unwrap_or(error())))),
+            "read" => Some(app(abs(F, app(F, read().unwrap_or(error()))), term.clone())),
+            "write" => Some(write(format, term).map(|_| id()).unwrap_or(error())),
+            _ => None,
         }
-        "rand" => Some(abs(F, app(F, rand(term).unwrap_or(error())))),
-        "read" => Some(app(abs(F, app(F, read().unwrap_or(error()))), term)),
-        "write" => Some(write(term).map(|_| id()).unwrap_or(error())),
-        _ => None,
     }
 }
 
-fn beep() {
-    print!("\x07");
+/// Prints the output of a side effect, which is an object among the JSON Lines of a trace.
+fn output(format: Format, text: &str) {
+    match format {
+        Format::Json => println!("{}", json::output(text)),
+        Format::Text | Format::Value => print!("{text}"),
+    }
 }
 
-fn rand(term: Term<'_>) -> Option<Term<'static>> {
+fn rand(term: &Term<'_>) -> Option<Term<'static>> {
     let (start, end): (u64, u64) = term.decode()?;
     if start == end {
         return None;
@@ -36,9 +51,9 @@ fn read() -> Option<Term<'static>> {
     Some(s.trim().parse::<u64>().ok()?.encode())
 }
 
-fn write(n: Term<'_>) -> Option<()> {
+fn write(format: Format, n: &Term<'_>) -> Option<()> {
     let n: u64 = n.decode()?;
-    println!("{n}");
+    output(format, &format!("{n}\n"));
     Some(())
 }
 
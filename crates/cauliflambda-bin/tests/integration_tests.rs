@@ -0,0 +1,100 @@
+use std::io::Write as _;
+use std::process::{Command, Output, Stdio};
+
+fn run(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cauliflambda"))
+        .args(args)
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn prints_normal_form_to_stdout() {
+    let output = run(&["run", "--no-prelude"], "(λx.x) y");
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!("y\n", String::from_utf8_lossy(&output.stdout));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Found normal form after 1 step\n"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn prints_nothing_to_stdout_without_normal_form() {
+    let output = run(
+        &[
+            "run",
+            "--no-prelude",
+            "--no-detect-cycles",
+            "--strategy",
+            "applicative-order",
+            "--max-steps",
+            "10",
+        ],
+        "(λx y.x) (λa.a) ((λx.x x) (λx.x x))",
+    );
+    assert_eq!(Some(4), output.status.code());
+    assert_eq!("", String::from_utf8_lossy(&output.stdout));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("(λx.x x) (λx.x x)"), "{stderr}");
+    assert!(
+        stderr.contains("Reached step limit after 10 steps"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn reports_cycles_unless_told_not_to() {
+    let omega = "(λx.x x) (λx.x x)";
+    let output = run(&["run", "--no-prelude"], omega);
+    assert_eq!(Some(4), output.status.code());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Term loops after 1 step "), "{stderr}");
+
+    let output = run(
+        &[
+            "run",
+            "--no-prelude",
+            "--no-detect-cycles",
+            "--max-steps",
+            "5",
+        ],
+        omega,
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Reached step limit after 5 steps"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn writes_every_step_as_json() {
+    let output = run(
+        &["run", "--no-prelude", "--format", "json"],
+        "I -> (λx.x)\n\nI y",
+    );
+    assert_eq!(Some(0), output.status.code());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(3, lines.len(), "{stdout}");
+    assert!(lines[0].starts_with(r#"{"type":"step","step":1,"kind":"delta""#));
+    assert!(
+        lines[0].contains(r#""location":{"file":"<stdin>","line":3,"column":1}"#),
+        "{stdout}"
+    );
+    assert!(lines[1].contains(r#""location":{"file":"<stdin>","line":3,"column":1}"#));
+    assert!(lines[2].starts_with(r#"{"type":"summary""#));
+}
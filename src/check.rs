@@ -0,0 +1,220 @@
+use crate::diagnostics::*;
+use crate::syntax::*;
+
+/// Finds the variables that are neither bound by an abstraction or `let` nor defined.
+///
+/// They stay free during the evaluation, which is rarely intended, so they are errors
+/// that suggest similar names that could be meant instead.
+pub fn check(program: &Program<'_>) -> Diagnostics {
+    let definitions: Vec<_> = (program.definitions.iter())
+        .map(|definition| definition.name.value)
+        .collect();
+    let mut errors = Vec::new();
+    let formulas = (program.definitions.iter())
+        .map(|definition| &definition.formula)
+        .chain([&program.formula]);
+    for formula in formulas {
+        visit_free(formula, &mut Vec::new(), &mut |variable, scope| {
+            let name = variable.value;
+            if definitions.contains(&name) {
+                return;
+            }
+            let error = Diagnostic::new(
+                DiagnosticSeverity::Error,
+                format!("unbound variable `{name}`"),
+            )
+            .with_label(Label::new(variable.span).with_message("not bound or defined"));
+            let candidates = scope.iter().chain(&definitions).copied();
+            errors.push(with_suggestions(error, name, candidates));
+        });
+    }
+    Diagnostics(errors)
+}
+
+/// Finds definitions that use themselves or definitions that come after them.
+///
+/// A definition can only use the definitions before it (see
+/// [`Definitions`](crate::evaluation::Definitions)), so these uses would stay free
+/// during the evaluation. Recursion needs a fixpoint combinator instead.
+pub fn check_definitions(definitions: &[NominalDefinition<'_>]) -> Diagnostics {
+    let mut errors = Vec::new();
+    for (index, definition) in definitions.iter().enumerate() {
+        let (earlier, later) = definitions.split_at(index);
+        visit_free(&definition.formula, &mut Vec::new(), &mut |variable, _| {
+            let name = variable.value;
+            if earlier.iter().any(|d| d.name.value == name) {
+                return;
+            }
+            if let Some(later) = later.iter().find(|d| d.name.value == name) {
+                errors.push(
+                    Diagnostic::new(
+                        DiagnosticSeverity::Error,
+                        format!("`{name}` is used before it is defined"),
+                    )
+                    .with_label(Label::new(variable.span).with_message("not defined yet"))
+                    .with_label(Label::new(later.name.span).with_message("defined here")),
+                );
+            }
+        });
+    }
+    Diagnostics(errors)
+}
+
+/// Adds the names among `candidates` that are closest to `name` as help.
+fn with_suggestions<'n>(
+    diagnostic: Diagnostic,
+    name: &str,
+    candidates: impl Iterator<Item = &'n str>,
+) -> Diagnostic {
+    match suggestions(name, candidates).as_slice() {
+        [] => diagnostic,
+        [suggestion] => diagnostic.with_help(format!("did you mean `{suggestion}`?")),
+        [suggestions @ .., last] => diagnostic.with_help(format!(
+            "did you mean `{}` or `{last}`?",
+            suggestions.join("`, `")
+        )),
+    }
+}
+
+/// Calls `f` with every variable in `formula` that isn't bound by an abstraction or `let`
+/// in it, together with the names that are bound where the variable is.
+fn visit_free<'a>(
+    formula: &Formula<'a>,
+    scope: &mut Vec<&'a str>,
+    f: &mut impl FnMut(&Identifier<'a>, &[&'a str]),
+) {
+    match formula {
+        Formula::Abs(abs) => {
+            scope.push(abs.variable.value);
+            visit_free(&abs.formula, scope, f);
+            scope.pop();
+        }
+        Formula::App(app) => {
+            visit_free(&app.left, scope, f);
+            visit_free(&app.right, scope, f);
+        }
+        Formula::Var(variable) => {
+            if !scope.contains(&variable.value) {
+                f(variable, scope);
+            }
+        }
+        Formula::List(list) => (list.items.iter()).for_each(|item| visit_free(item, scope, f)),
+        Formula::Let(binding) => {
+            if binding.recursive {
+                scope.push(binding.name.value);
+                visit_free(&binding.value, scope, f);
+            } else {
+                visit_free(&binding.value, scope, f);
+                scope.push(binding.name.value);
+            }
+            visit_free(&binding.body, scope, f);
+            scope.pop();
+        }
+        Formula::Sym(_) | Formula::Num(_) | Formula::Str(_) | Formula::Error(_) => {}
+    }
+}
+
+/// The closest names to `name` that are similar enough to be typos of each other.
+///
+/// Names that share no character with `name`, like two different single letters, aren't.
+fn suggestions<'n>(name: &str, candidates: impl Iterator<Item = &'n str>) -> Vec<&'n str> {
+    const MAX_SUGGESTIONS: usize = 3;
+    let length = name.chars().count();
+    let max_distance = (length / 3).max(1).min(length - 1);
+    let mut suggestions: Vec<_> = candidates
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    suggestions.sort();
+    suggestions.dedup();
+    let closest = suggestions.first().map(|(distance, _)| *distance);
+    suggestions
+        .into_iter()
+        .take_while(|(distance, _)| Some(*distance) == closest)
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+/// The Levenshtein distance, i.e. how many characters have to be inserted, deleted or
+/// substituted to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<_> = b.chars().collect();
+    let mut previous: Vec<_> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_program;
+
+    fn errors(input: &str) -> Vec<Diagnostic> {
+        check(&parse_program(input).unwrap().value).0
+    }
+
+    #[test]
+    fn resolves_binders_and_definitions() {
+        let input = "I -> (λx.x)\nR -> (R)\nlet rec f = λn.f (I n) in f y where y = :a";
+        assert!(errors(input).is_empty());
+    }
+
+    #[test]
+    fn reports_unbound_variables() {
+        let errors = errors("λx.x y (λy.y) (let z = z in z)");
+        let messages: Vec<_> = errors.iter().map(|error| &error.message).collect();
+        assert_eq!(
+            vec!["unbound variable `y`", "unbound variable `z`"],
+            messages
+        );
+        assert_eq!(Span { start: 6, end: 7 }, errors[0].labels[0].location);
+    }
+
+    #[test]
+    fn suggests_similar_names() {
+        let errors = errors("incr -> (λn.n)\nλx.incrr x (λxs.xx) (lenght Y)");
+        let notes: Vec<_> = errors.iter().map(|error| error.notes.clone()).collect();
+        assert_eq!(
+            vec![
+                vec!["help: did you mean `incr`?"],
+                vec!["help: did you mean `x` or `xs`?"],
+                vec![],
+                vec![],
+            ],
+            notes
+        );
+    }
+
+    #[test]
+    fn reports_uses_before_definitions() {
+        let input = "A -> (λx.B x)\nB -> (λx.x)\nf -> (λn.f n)\nB -> (λx.B x)\nZ -> (let rec g = g in g)\nA";
+        let program = parse_program(input).unwrap().value;
+        let errors = check_definitions(&program.definitions).0;
+        let messages: Vec<_> = errors.iter().map(|error| &error.message).collect();
+        assert_eq!(
+            vec![
+                "`B` is used before it is defined",
+                "`f` is used before it is defined"
+            ],
+            messages
+        );
+        assert_eq!(Span { start: 15, end: 16 }, errors[0].labels[1].location);
+    }
+
+    #[test]
+    fn measures_edit_distance() {
+        assert_eq!(0, edit_distance("map", "map"));
+        assert_eq!(1, edit_distance("map", "mapp"));
+        assert_eq!(2, edit_distance("length", "lenght"));
+        assert_eq!(3, edit_distance("", "abc"));
+        assert_eq!(1, edit_distance("λ", "x"));
+    }
+}
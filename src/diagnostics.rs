@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::fmt;
 use std::ops::Deref;
 
 use crate::syntax::Span;
@@ -42,7 +43,10 @@ pub struct Diagnostics(pub Vec<Diagnostic>);
 pub struct Diagnostic {
     pub severity: DiagnosticSeverity,
     pub message: Cow<'static, str>,
+    /// The first label is where the problem is, the others point to related places.
     pub labels: Vec<Label>,
+    /// Additional explanations, e.g. `help: …` with a suggestion.
+    pub notes: Vec<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -58,6 +62,7 @@ impl Diagnostic {
             severity,
             message: message.into(),
             labels: Vec::default(),
+            notes: Vec::default(),
         }
     }
 
@@ -65,6 +70,11 @@ impl Diagnostic {
         self.labels.push(label);
         self
     }
+
+    pub(crate) fn with_help(mut self, help: impl fmt::Display) -> Self {
+        self.notes.push(format!("help: {help}"));
+        self
+    }
 }
 
 impl Label {
@@ -74,6 +84,13 @@ impl Label {
             message: None,
         }
     }
+
+    pub(crate) fn with_message(self, message: impl Into<String>) -> Self {
+        Label {
+            message: Some(message.into()),
+            ..self
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
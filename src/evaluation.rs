@@ -1,5 +1,6 @@
-use crate::syntax;
+use crate::syntax::{self, Span};
 use std::fmt::{self, Write};
+use std::hash::{Hash, Hasher};
 
 #[macro_use]
 mod macros;
@@ -13,16 +14,46 @@ mod free;
 pub use free::*;
 mod beta;
 pub(crate) use beta::*;
+mod delta;
+pub use delta::*;
+mod environment;
+pub(crate) use environment::*;
 mod rename_bound;
 pub use rename_bound::*;
 mod result;
 pub use result::*;
 mod substitute;
 pub use substitute::*;
+mod applicative_order;
+mod call_by_name;
+mod call_by_need;
+pub use call_by_need::evaluate_by_need;
+mod call_by_value;
+mod head;
+mod strategy;
+pub use strategy::*;
+mod options;
+pub use options::*;
+mod de_bruijn;
+pub use de_bruijn::*;
+mod alpha_eq;
+pub use alpha_eq::*;
+mod cycle;
+use cycle::*;
+mod graph;
+pub use graph::*;
+#[cfg(feature = "optimal")]
+mod optimal;
+#[cfg(feature = "optimal")]
+pub use optimal::*;
+mod path;
+pub use path::*;
 mod side_effects;
 pub use side_effects::*;
 mod church_numerals;
 mod encoding;
+mod lists;
+pub use lists::*;
 mod tuple;
 pub use encoding::*;
 mod church_booleans;
@@ -44,6 +75,12 @@ pub fn abs<'a>(variable: impl Into<Variable<'a>>, term: impl Into<Term<'a>>) ->
     Term::Abs(Box::new(Abstraction::new(variable.into(), term.into())))
 }
 
+/// Parses a term, keeping the spans which (unlike with `λ!`) refer to `input` itself.
+#[cfg(test)]
+fn parse_term(input: &str) -> Term<'_> {
+    crate::parse_formula(input).unwrap().value.into()
+}
+
 #[cfg(test)]
 fn nested_abs<'a, I, V>(variables: I, term: Term<'a>) -> Term<'a>
 where
@@ -56,23 +93,92 @@ where
         .rfold(term, |term, v| abs(v.into(), term))
 }
 
+/// A term together with the definitions that may be expanded while evaluating it.
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[non_exhaustive]
+pub struct Program<'a> {
+    pub definitions: Definitions<'a>,
+    pub term: Term<'a>,
+}
+
+impl<'a> Program<'a> {
+    pub fn new(definitions: Definitions<'a>, term: Term<'a>) -> Self {
+        Program { definitions, term }
+    }
+}
+
+impl<'a> From<syntax::Program<'a>> for Program<'a> {
+    fn from(value: syntax::Program<'a>) -> Self {
+        Self::new(value.definitions.into(), value.formula.into())
+    }
+}
+
+impl<'a> From<syntax::Formula<'a>> for Program<'a> {
+    fn from(formula: syntax::Formula<'a>) -> Self {
+        Term::from(formula).into()
+    }
+}
+
+impl<'a> From<Term<'a>> for Program<'a> {
+    fn from(term: Term<'a>) -> Self {
+        Self::new(Definitions::default(), term)
+    }
+}
+
+/// A single step of an evaluation. Like terms, steps are compared without their spans
+/// and paths.
+#[derive(Debug, Clone, Eq)]
+#[non_exhaustive]
 pub struct Step<'a> {
     pub term: Term<'a>,
     pub kind: StepKind,
+    /// Where the contracted redex (or the expanded definition) came from in the source, if known.
+    pub redex: Option<Span>,
+    /// Where the contracted redex is in the term before this step, if known.
+    pub redex_path: Option<Path>,
+    /// Where the result of the contraction is in [`Step::term`], if known.
+    pub contractum_path: Option<Path>,
 }
 
 impl<'a> Step<'a> {
     fn new(kind: StepKind, term: Term<'a>) -> Self {
-        Step { term, kind }
+        Step {
+            term,
+            kind,
+            redex: None,
+            redex_path: None,
+            contractum_path: None,
+        }
     }
 
-    fn map(mut self, f: impl FnOnce(Term<'a>) -> Term<'a>) -> Self {
-        self.term = f(self.term);
+    /// Marks this step as the contraction of the whole term.
+    fn at_redex(self, redex: Option<Span>) -> Self {
+        Step {
+            redex,
+            redex_path: Some(Path::default()),
+            contractum_path: Some(Path::default()),
+            ..self
+        }
+    }
+
+    /// Moves the redex and contractum of this step below `directions`.
+    fn within(mut self, directions: &[Direction]) -> Self {
+        for path in [&mut self.redex_path, &mut self.contractum_path]
+            .into_iter()
+            .flatten()
+        {
+            path.prepend(directions);
+        }
         self
     }
 
+    /// Wraps the term of this step, which is found below `directions` in the wrapped term.
+    fn map(self, directions: &[Direction], f: impl FnOnce(Term<'a>) -> Term<'a>) -> Self {
+        let mut step = self.within(directions);
+        step.term = f(step.term);
+        step
+    }
+
     fn id_or_err(self) -> Result<Term<'a>, Self> {
         if self.kind == StepKind::Id {
             Ok(self.term)
@@ -86,6 +192,12 @@ impl<'a> Step<'a> {
     }
 }
 
+impl<'a> PartialEq for Step<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.term == other.term && self.kind == other.kind
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum StepKind {
     /// No operation was performed.
@@ -98,6 +210,12 @@ pub enum StepKind {
     Delta,
     /// Side Effect
     SideEffect,
+    /// Call-by-need: a needed variable is replaced by the value it is bound to.
+    Deref,
+    /// Call-by-need: a let-binding is lifted out of the function position of an application.
+    Lift,
+    /// Call-by-need: a let-binding is moved out of the argument of another let-binding.
+    Assoc,
 }
 
 impl fmt::Display for StepKind {
@@ -108,6 +226,9 @@ impl fmt::Display for StepKind {
             StepKind::Beta => f.write_str("β"),
             StepKind::Delta => f.write_str("δ"),
             StepKind::SideEffect => f.write_str("!"),
+            StepKind::Deref => f.write_str("deref"),
+            StepKind::Lift => f.write_str("lift"),
+            StepKind::Assoc => f.write_str("assoc"),
         }
     }
 }
@@ -118,14 +239,63 @@ pub enum Term<'a> {
     App(Box<Application<'a>>),
 }
 
+impl<'a> Term<'a> {
+    /// The number of variables, abstractions and applications in this term.
+    pub fn size(&self) -> usize {
+        match self {
+            Term::Var(_) => 1,
+            Term::Abs(abs) => 1 + abs.term.size(),
+            Term::App(app) => 1 + app.left.size() + app.right.size(),
+        }
+    }
+
+    /// Where this term came from in the source, if known.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Term::Var(v) => v.span,
+            Term::Abs(a) => a.span,
+            Term::App(a) => a.span,
+        }
+    }
+
+    /// Replaces the span of this term (but not the spans of its subterms).
+    pub fn with_span(self, span: Option<Span>) -> Self {
+        match self {
+            Term::Var(v) => Term::Var(Variable { span, ..v }),
+            Term::Abs(mut a) => {
+                a.span = span;
+                Term::Abs(a)
+            }
+            Term::App(mut a) => {
+                a.span = span;
+                Term::App(a)
+            }
+        }
+    }
+}
+
 impl<'a> From<syntax::Formula<'a>> for Term<'a> {
     fn from(value: syntax::Formula<'a>) -> Self {
         match value {
             syntax::Formula::Abs(abs) => Term::Abs(Box::new((*abs).into())),
             syntax::Formula::App(app) => Term::App(Box::new((*app).into())),
             syntax::Formula::Var(var) => Term::Var(var.into()),
-            syntax::Formula::Sym(sym) => {
-                Term::Var(Variable::new_with(sym.ident.value, Disambiguator::Symbol))
+            syntax::Formula::Sym(sym) => Term::Var(
+                Variable::new_with(sym.ident.value, Disambiguator::Symbol)
+                    .with_span(Some(sym.span)),
+            ),
+            syntax::Formula::Num(num) => num.value.encode().with_span(Some(num.span)),
+            syntax::Formula::Let(binding) => binding.desugar().into(),
+            syntax::Formula::List(list) => {
+                let items = list.items.into_iter().map(Term::from).collect();
+                encode_list(items, list.encoding).with_span(Some(list.span))
+            }
+            syntax::Formula::Str(text) => {
+                let characters = text.value.chars().map(|c| u64::from(c).encode()).collect();
+                encode_list(characters, text.encoding).with_span(Some(text.span))
+            }
+            syntax::Formula::Error(span) => {
+                Term::Var(Variable::new_with("error", Disambiguator::Symbol).with_span(Some(span)))
             }
         }
     }
@@ -172,16 +342,34 @@ impl<'a> fmt::Display for Term<'a> {
 /// A free or bound variable. A variable can have a disambiguator that
 /// is incremented during evaluation to avoid conflicting names.
 ///
+/// Variables (like abstractions and applications) remember where they came from
+/// in the source. This span is ignored when comparing or hashing variables.
+///
 /// Example: `x`.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, Eq)]
 #[non_exhaustive]
 pub struct Variable<'a> {
     pub name: &'a str,
     pub disambiguator: Disambiguator,
+    pub span: Option<Span>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+impl<'a> PartialEq for Variable<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.disambiguator == other.disambiguator
+    }
+}
+
+impl<'a> Hash for Variable<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.disambiguator.hash(state);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum Disambiguator {
+    #[default]
     None,
     /// A symbol is guaranteed to be always free.
     Symbol,
@@ -189,12 +377,6 @@ pub enum Disambiguator {
     Numeric(u64),
 }
 
-impl Default for Disambiguator {
-    fn default() -> Self {
-        Disambiguator::None
-    }
-}
-
 impl From<u64> for Disambiguator {
     fn from(value: u64) -> Self {
         Disambiguator::Numeric(value)
@@ -206,6 +388,7 @@ impl<'a> Variable<'a> {
         Variable {
             name,
             disambiguator: Disambiguator::None,
+            span: None,
         }
     }
 
@@ -213,6 +396,7 @@ impl<'a> Variable<'a> {
         Variable {
             name,
             disambiguator: d.into(),
+            span: None,
         }
     }
 
@@ -222,11 +406,15 @@ impl<'a> Variable<'a> {
             ..self
         }
     }
+
+    pub fn with_span(self, span: Option<Span>) -> Self {
+        Self { span, ..self }
+    }
 }
 
 impl<'a> From<syntax::Identifier<'a>> for Variable<'a> {
     fn from(value: syntax::Identifier<'a>) -> Self {
-        Self::new(value.value)
+        Self::new(value.value).with_span(Some(value.span))
     }
 }
 
@@ -255,22 +443,37 @@ impl<'a> fmt::Display for Variable<'a> {
 /// An abstraction (or anonymous function definition).
 ///
 /// Example: `λx.x`.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Eq)]
 #[non_exhaustive]
 pub struct Abstraction<'a> {
     pub variable: Variable<'a>,
     pub term: Term<'a>,
+    pub span: Option<Span>,
 }
 
 impl<'a> Abstraction<'a> {
     pub fn new(variable: Variable<'a>, term: Term<'a>) -> Self {
-        Abstraction { variable, term }
+        Abstraction {
+            variable,
+            term,
+            span: None,
+        }
+    }
+
+    pub fn with_span(self, span: Option<Span>) -> Self {
+        Self { span, ..self }
+    }
+}
+
+impl<'a> PartialEq for Abstraction<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.variable == other.variable && self.term == other.term
     }
 }
 
 impl<'a> From<syntax::Abstraction<'a>> for Abstraction<'a> {
     fn from(value: syntax::Abstraction<'a>) -> Self {
-        Self::new(value.variable.into(), value.formula.into())
+        Self::new(value.variable.into(), value.formula.into()).with_span(Some(value.span))
     }
 }
 
@@ -283,22 +486,37 @@ impl<'a> fmt::Display for Abstraction<'a> {
 /// A function application where the term on the right is applied to the term on the left.
 ///
 /// Example: `(λx.x) Y`.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Eq)]
 #[non_exhaustive]
 pub struct Application<'a> {
     pub left: Term<'a>,
     pub right: Term<'a>,
+    pub span: Option<Span>,
 }
 
 impl<'a> Application<'a> {
     pub fn new(left: Term<'a>, right: Term<'a>) -> Self {
-        Application { left, right }
+        Application {
+            left,
+            right,
+            span: None,
+        }
+    }
+
+    pub fn with_span(self, span: Option<Span>) -> Self {
+        Self { span, ..self }
+    }
+}
+
+impl<'a> PartialEq for Application<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.left == other.left && self.right == other.right
     }
 }
 
 impl<'a> From<syntax::Application<'a>> for Application<'a> {
     fn from(value: syntax::Application<'a>) -> Self {
-        Self::new(value.left.into(), value.right.into())
+        Self::new(value.left.into(), value.right.into()).with_span(Some(value.span))
     }
 }
 
@@ -331,6 +549,52 @@ fn with_parenthesis(
 mod tests {
     use super::*;
 
+    #[test]
+    fn counts_every_node_towards_term_size() {
+        assert_eq!(1, var("x").size());
+        assert_eq!(6, λ![(λx.x x) y].size());
+    }
+
+    #[test]
+    fn keeps_spans_of_parsed_formula() {
+        let term = parse_term(r"(\x.x) y");
+        let App! { left: Abs! { variable, term: body, span: abs_span }, right, span } = &term
+        else {
+            unreachable!()
+        };
+        assert_eq!(Some(Span::from(1..8)), *span);
+        assert_eq!(Some(Span::from(1..5)), *abs_span);
+        assert_eq!(Some(Span::from(2..3)), variable.span);
+        assert_eq!(Some(Span::from(4..5)), body.span());
+        assert_eq!(Some(Span::from(7..8)), right.span());
+    }
+
+    #[test]
+    fn desugars_let_bindings() {
+        assert_eq!(λ![(λx.x B) A], parse_term("let x = A in x B"));
+        assert_eq!(λ![(λx.x B) A], parse_term("x B where x = A"));
+        let z = λ![λf.(λx.f (λv.x x v)) (λx.f (λv.x x v))];
+        assert_eq!(
+            app(abs("f", var("f")), app(z, λ![λf.λn.f n])),
+            parse_term("let rec f = λn.f n in f")
+        );
+    }
+
+    #[test]
+    fn desugared_let_bindings_have_span_of_binding() {
+        let App! { left: Abs! { span: abs_span }, span } = parse_term("(let x = A in x)") else {
+            unreachable!()
+        };
+        assert_eq!(Some(Span::from(1..15)), span);
+        assert_eq!(Some(Span::from(1..15)), abs_span);
+    }
+
+    #[test]
+    fn ignores_spans_when_comparing() {
+        assert_eq!(λ![λx.x y], λ![λx.  x   y]);
+        assert_eq!(abs("x", app(var("x"), var("y"))), λ![λx.x y]);
+    }
+
     #[test]
     fn only_adds_necessary_parenthesis() {
         let terms = [
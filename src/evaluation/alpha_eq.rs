@@ -0,0 +1,163 @@
+use super::*;
+use std::hash::{Hash, Hasher};
+use Term::*;
+
+/// Compares two terms for α-equivalence, i.e. equality up to the names of bound variables.
+/// Free variables (including symbols) must match exactly.
+///
+/// Example: `λx.x y` and `λz.z y` are α-equivalent, `λx.x y` and `λx.x z` are not.
+pub fn alpha_eq(left: &Term, right: &Term) -> bool {
+    alpha_eq_in(left, right, &mut Vec::new(), &mut Vec::new())
+}
+
+fn alpha_eq_in<'a>(
+    left: &Term<'a>,
+    right: &Term<'a>,
+    left_binders: &mut Vec<Variable<'a>>,
+    right_binders: &mut Vec<Variable<'a>>,
+) -> bool {
+    match (left, right) {
+        (Var(left), Var(right)) => {
+            match (index_of(left, left_binders), index_of(right, right_binders)) {
+                (None, None) => left == right,
+                (left_index, right_index) => left_index == right_index,
+            }
+        }
+        (Abs(left), Abs(right)) => {
+            left_binders.push(left.variable);
+            right_binders.push(right.variable);
+            let equal = alpha_eq_in(&left.term, &right.term, left_binders, right_binders);
+            left_binders.pop();
+            right_binders.pop();
+            equal
+        }
+        (App(left), App(right)) => {
+            alpha_eq_in(&left.left, &right.left, left_binders, right_binders)
+                && alpha_eq_in(&left.right, &right.right, left_binders, right_binders)
+        }
+        _ => false,
+    }
+}
+
+/// The number of abstractions between a bound variable and its binder.
+fn index_of(variable: &Variable, binders: &[Variable]) -> Option<usize> {
+    if variable.disambiguator == Disambiguator::Symbol {
+        None
+    } else {
+        binders.iter().rev().position(|b| b == variable)
+    }
+}
+
+/// A term that is compared and hashed up to α-equivalence (see [`alpha_eq`]),
+/// e.g. for use as a key in a [`HashMap`](std::collections::HashMap).
+#[derive(Debug, Clone)]
+pub struct AlphaEq<'a>(pub Term<'a>);
+
+impl<'a> PartialEq for AlphaEq<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        alpha_eq(&self.0, &other.0)
+    }
+}
+
+impl<'a> Eq for AlphaEq<'a> {}
+
+impl<'a> Hash for AlphaEq<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_alpha(&self.0, state)
+    }
+}
+
+/// Hashes a term such that α-equivalent terms have the same hash.
+pub(crate) fn hash_alpha(term: &Term, state: &mut impl Hasher) {
+    hash_in(term, &mut Vec::new(), state)
+}
+
+fn hash_in<'a>(term: &Term<'a>, binders: &mut Vec<Variable<'a>>, state: &mut impl Hasher) {
+    match term {
+        Var(variable) => match index_of(variable, binders) {
+            Some(index) => (0u8, index).hash(state),
+            None => (1u8, variable).hash(state),
+        },
+        Abs! { variable, term } => {
+            2u8.hash(state);
+            binders.push(*variable);
+            hash_in(term, binders, state);
+            binders.pop();
+        }
+        App! { left, right } => {
+            3u8.hash(state);
+            hash_in(left, binders, state);
+            hash_in(right, binders, state);
+        }
+    }
+}
+
+impl<'a> From<Term<'a>> for AlphaEq<'a> {
+    fn from(term: Term<'a>) -> Self {
+        AlphaEq(term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn ignores_names_of_bound_variables() {
+        assert!(alpha_eq(&λ![λx.x], &λ![λy.y]));
+        assert!(alpha_eq(&λ![λx.λy.x y z], &λ![λa.λb.a b z]));
+        assert!(alpha_eq(&abs(("x", 1), var_with("x", 1)), &λ![λx.x]));
+    }
+
+    #[test]
+    fn respects_binding_structure() {
+        assert!(!alpha_eq(&λ![λx.λy.x], &λ![λx.λy.y]));
+        assert!(!alpha_eq(&λ![λx.λx.x], &λ![λx.λy.x]));
+        assert!(!alpha_eq(&λ![λx.y], &λ![λy.y]));
+    }
+
+    #[test]
+    fn compares_free_variables_by_name() {
+        assert!(alpha_eq(&λ![x y], &λ![x y]));
+        assert!(!alpha_eq(&λ![x], &λ![y]));
+        assert!(!alpha_eq(&var("x"), &var_with("x", 1)));
+    }
+
+    #[test]
+    fn respects_symbols() {
+        assert!(!alpha_eq(&λ![:x], &λ![x]));
+        let symbol = Variable::new_with("x", Disambiguator::Symbol);
+        assert!(!alpha_eq(&abs(symbol, symbol), &abs("x", var("x"))));
+    }
+
+    #[test]
+    fn hashes_alpha_equivalent_terms_equally() {
+        let terms = HashSet::from([
+            AlphaEq(λ![λx.x]),
+            AlphaEq(λ![λy.y]),
+            AlphaEq(λ![λx.λy.x]),
+            AlphaEq(λ![λa.λb.a]),
+            AlphaEq(λ![λx.λy.y]),
+        ]);
+        assert_eq!(3, terms.len());
+    }
+
+    #[test]
+    fn agrees_with_de_bruijn_representation() {
+        let terms = [
+            λ![λx.x],
+            λ![λy.y],
+            λ![λx.λx.x],
+            λ![λx.λy.x],
+            λ![λx.y],
+            λ![λy.y x],
+        ];
+        for left in &terms {
+            for right in &terms {
+                let nameless = DeBruijnTerm::from(left) == DeBruijnTerm::from(right);
+                assert_eq!(nameless, alpha_eq(left, right), "{left} = {right}");
+            }
+        }
+    }
+}
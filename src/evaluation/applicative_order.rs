@@ -0,0 +1,54 @@
+//! Leftmost-innermost reduction to normal form: the function and its argument are
+//! reduced to normal form before the application itself is contracted.
+use super::*;
+use Term::*;
+
+pub(crate) fn reduce<'a>(
+    term: impl Into<Term<'a>>,
+    environment: &mut Environment<'a, impl EffectHandler<'a>>,
+) -> Step<'a> {
+    match term.into() {
+        App! { left, right, span } => match reduce(left, environment).id_or_err() {
+            Ok(left) => match reduce(right, environment).id_or_err() {
+                Ok(right) => match left {
+                    Abs(abs) => rename_and_substitute(*abs, right, span),
+                    left => perform(left, right, span, environment),
+                },
+                Err(right) => right.map(&[Direction::Right], |right| {
+                    app(left, right).with_span(span)
+                }),
+            },
+            Err(left) => left.map(&[Direction::Left], |left| app(left, right).with_span(span)),
+        },
+        Abs! { variable, term, span } => reduce(term, environment)
+            .map(&[Direction::Body], |term| {
+                abs(variable, term).with_span(span)
+            }),
+        Var(variable) => expand(variable, &environment.definitions),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reduces_argument_before_contracting_application() {
+        let expression = λ![ (λx.x) ((λy.y) Y) ];
+        let expected = λ![ (λx.x) Y ];
+        assert_eq!(
+            expected,
+            reduce(expression, &mut Environment::<NoSideEffects>::default()).term
+        );
+    }
+
+    #[test]
+    fn reduces_function_body_before_argument() {
+        let expression = λ![ (λx.(λy.y) x) ((λz.z) Z) ];
+        let expected = λ![ (λx.x) ((λz.z) Z) ];
+        assert_eq!(
+            expected,
+            reduce(expression, &mut Environment::<NoSideEffects>::default()).term
+        );
+    }
+}
@@ -3,30 +3,61 @@ use StepKind::*;
 use Term::*;
 use TermResult::*;
 
-pub(crate) fn reduce<'a>(term: impl Into<Term<'a>>) -> Step<'a> {
+pub(crate) fn reduce<'a>(
+    term: impl Into<Term<'a>>,
+    environment: &mut Environment<'a, impl EffectHandler<'a>>,
+) -> Step<'a> {
     match term.into() {
-        App! { left: Abs(abs), right } => rename_and_substitute(abs.term, abs.variable, right),
-        App! { left, right } => recurse(left, right),
-        Abs! { variable, term } => reduce(term).map(|term| abs(variable, term)),
-        term => Step::new(Id, term),
+        App! { left: Abs(abs), right, span } => rename_and_substitute(*abs, right, span),
+        App! { left, right, span } => recurse(left, right, span, environment),
+        Abs! { variable, term, span } => reduce(term, environment)
+            .map(&[Direction::Body], |term| {
+                abs(variable, term).with_span(span)
+            }),
+        Var(variable) => expand(variable, &environment.definitions),
     }
 }
 
-fn recurse<'a>(left: Term<'a>, right: Term<'a>) -> Step<'a> {
-    match reduce(left).id_or_err() {
-        Ok(left) => reduce(right).map(|right| app(left, right)),
-        Err(left) => left.map(|left| app(left, right)),
+fn recurse<'a>(
+    left: Term<'a>,
+    right: Term<'a>,
+    span: Option<Span>,
+    environment: &mut Environment<'a, impl EffectHandler<'a>>,
+) -> Step<'a> {
+    match reduce(left, environment).id_or_err() {
+        Ok(left) => match reduce(right, environment).id_or_err() {
+            Ok(right) => perform(left, right, span, environment),
+            Err(right) => right.map(&[Direction::Right], |right| {
+                app(left, right).with_span(span)
+            }),
+        },
+        Err(left) => left.map(&[Direction::Left], |left| app(left, right).with_span(span)),
     }
 }
 
-fn rename_and_substitute<'a>(haystack: Term<'a>, needle: Variable<'a>, term: Term<'a>) -> Step<'a> {
-    match rename_bound(haystack, is_bound_in(&term)) {
-        Modified(haystack) => Step::new(Alpha, app(abs(needle, haystack), term)),
-        Original(input) => Step::new(Beta, substitute(needle, &term, input)),
-    }
+/// Contracts the redex `function argument` that has the given span,
+/// unless its bound variables have to be renamed first.
+pub(crate) fn rename_and_substitute<'a>(
+    function: Abstraction<'a>,
+    argument: Term<'a>,
+    span: Option<Span>,
+) -> Step<'a> {
+    let Abstraction {
+        variable,
+        term,
+        span: function_span,
+    } = function;
+    let step = match rename_bound(term, is_bound_in(&argument)) {
+        Modified(term) => {
+            let function = abs(variable, term).with_span(function_span);
+            Step::new(Alpha, app(function, argument).with_span(span))
+        }
+        Original(term) => Step::new(Beta, substitute(variable, &argument, term)),
+    };
+    step.at_redex(span)
 }
 
-fn is_bound_in<'a>(term: &'a Term) -> impl Fn(&Variable) -> bool + Clone + 'a {
+pub(crate) fn is_bound_in<'a>(term: &'a Term) -> impl Fn(&Variable) -> bool + Clone + 'a {
     let is_free = is_free_in(term);
     move |v| !is_free(v)
 }
@@ -39,37 +70,85 @@ mod tests {
     fn detects_expressions_that_cannot_be_reduced_futher() {
         let expressions = [λ![x], λ![λa.λb.λc.c], λ![λx.x x (x x)], λ![X(λx.λy.x)]];
         for expression in expressions {
-            let result = dbg!(reduce(expression.clone()));
+            let result = dbg!(reduce(
+                expression.clone(),
+                &mut Environment::<NoSideEffects>::default()
+            ));
             assert_eq!(StepKind::Id, result.kind);
             assert_eq!(expression, result.term);
         }
     }
 
+    #[test]
+    fn records_span_of_contracted_redex() {
+        let expression = parse_term(r"X ((\x.x) Y)");
+        let step = reduce(expression, &mut Environment::<NoSideEffects>::default());
+        assert_eq!(Some(Span::from(4..11)), step.redex);
+        assert_eq!(Some(Span::from(0..11)), step.term.span());
+    }
+
+    #[test]
+    fn records_path_of_contracted_redex() {
+        let step = reduce(
+            λ![X (λy.(λx.x) Y)],
+            &mut Environment::<NoSideEffects>::default(),
+        );
+        let path = Path::from(vec![Direction::Right, Direction::Body]);
+        assert_eq!(Some(&path), step.redex_path.as_ref());
+        assert_eq!(Some(&path), step.contractum_path.as_ref());
+        assert_eq!(Some(&var("Y")), path.get(&step.term));
+    }
+
     #[test]
     fn reduces_leftmost_application_first() {
         let expression = λ![ ((λx.x) X) ((λx.x) Y) ];
         let expected = λ![ X ((λx.x) Y) ];
-        assert_eq!(expected, reduce(expression).term);
+        assert_eq!(
+            expected,
+            reduce(expression, &mut Environment::<NoSideEffects>::default()).term
+        );
     }
 
     #[test]
     fn reduces_outermost_application_first() {
         let expression = λ![ (λx.(λy.y) x) X ];
         let expected = λ![ (λy.y) X ];
-        assert_eq!(expected, reduce(expression).term);
+        assert_eq!(
+            expected,
+            reduce(expression, &mut Environment::<NoSideEffects>::default()).term
+        );
     }
 
     #[test]
     fn reduces_leftmost_outermost_application_first() {
         let expression = λ![ ((λx.(λy.y) x) X) ((λx.x) Y) ];
         let expected = λ![ ((λy.y) X) ((λx.x) Y) ];
-        assert_eq!(expected, reduce(expression).term);
+        assert_eq!(
+            expected,
+            reduce(expression, &mut Environment::<NoSideEffects>::default()).term
+        );
     }
 
     #[test]
     fn reduces_application_with_naming_conflict() {
         let expression = λ![ (λy.λx.y) x ];
         let expected = abs(("x", 1), var("x"));
-        assert_eq!(expected, reduce(reduce(expression).term).term);
+        assert_eq!(
+            expected,
+            reduce(
+                reduce(expression, &mut Environment::<NoSideEffects>::default()).term,
+                &mut Environment::<NoSideEffects>::default()
+            )
+            .term
+        );
+    }
+
+    #[test]
+    fn reduces_application_with_naming_conflict_without_capturing() {
+        let expression = λ![ (λy.λx.y) x ];
+        let mut environment = Environment::<NoSideEffects>::default();
+        let renamed = reduce(expression, &mut environment).term;
+        let reduced = reduce(renamed, &mut environment).term;
+        assert!(alpha_eq(&λ![λz.x], &reduced), "{reduced}");
     }
 }
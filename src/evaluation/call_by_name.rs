@@ -0,0 +1,47 @@
+//! Call-by-name reduction to weak head normal form: arguments are substituted unevaluated
+//! and evaluation stops as soon as the term is an abstraction or has a variable in head position.
+use super::*;
+use StepKind::*;
+use Term::*;
+
+pub(crate) fn reduce<'a>(
+    term: impl Into<Term<'a>>,
+    environment: &mut Environment<'a, impl EffectHandler<'a>>,
+) -> Step<'a> {
+    match term.into() {
+        App! { left: Abs(abs), right, span } => rename_and_substitute(*abs, right, span),
+        App! { left, right, span } => match reduce(left, environment).id_or_err() {
+            Ok(left) if is_symbol(&left) => perform_with(left, right, span, environment, reduce),
+            Ok(left) => Step::new(Id, app(left, right).with_span(span)),
+            Err(left) => left.map(&[Direction::Left], |left| app(left, right).with_span(span)),
+        },
+        term @ Abs(_) => Step::new(Id, term),
+        Var(variable) => expand(variable, &environment.definitions),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substitutes_argument_unevaluated() {
+        let expression = λ![ (λx.x x) ((λy.y) Y) ];
+        let expected = λ![ (λy.y) Y ((λy.y) Y) ];
+        assert_eq!(
+            expected,
+            reduce(expression, &mut Environment::<NoSideEffects>::default()).term
+        );
+    }
+
+    #[test]
+    fn stops_at_weak_head_normal_form() {
+        let expressions = [λ![λx.(λy.y) x], λ![X ((λy.y) Y)]];
+        for expression in expressions {
+            assert_eq!(
+                Id,
+                reduce(expression, &mut Environment::<NoSideEffects>::default()).kind
+            );
+        }
+    }
+}
@@ -0,0 +1,463 @@
+//! Implementation of the Call-By-Need Lambda Calculus by Z. M. Ariola and M. Felleisen.
+//! See: <https://www.cambridge.org/core/services/aop-cambridge-core/content/view/F4FC3C34E9CAE3F4326503E254FCF6F2/S0956796897002724a.pdf/the-call-by-need-lambda-calculus.pdf>
+//!
+//! The calculus treats every application of an abstraction `(λx.M)N` as a `let x = N in M`.
+//! The argument is only evaluated once `x` is needed and the resulting value is then shared
+//! between all occurrences of `x`.
+//!
+//! Values and answers are subsets of expressions:
+//! ```text
+//! V ::= λx.M
+//! A ::= V | (λx.A)M
+//! ```
+//!
+//! Once the term is an answer (or gets stuck on a free variable) the
+//! remaining `let`s are substituted and evaluation continues below abstractions
+//! and in arguments of free variables so that the result is in normal form.
+use super::*;
+use Direction::*;
+use StepKind::*;
+use Term::*;
+use TermResult::*;
+
+/// Evaluates a program's term using call-by-need evaluation.
+pub fn evaluate_by_need<'a>(program: impl Into<Program<'a>>) -> impl Iterator<Item = Step<'a>> {
+    EvaluationStrategy::CallByNeed.evaluate(program)
+}
+
+pub(crate) fn reduce<'a>(
+    term: impl Into<Term<'a>>,
+    environment: &mut Environment<'a, impl EffectHandler<'a>>,
+) -> Step<'a> {
+    match reduce_weak(term.into()) {
+        Weak::Reduced(step) => step,
+        Weak::Demands(variable, context) => reduce_demand(variable, context, environment),
+        Weak::Answer(answer) => reduce_answer(answer, environment),
+    }
+}
+
+fn reduce_demand<'a>(
+    variable: Variable<'a>,
+    context: EvaluationContext<'a>,
+    environment: &mut Environment<'a, impl EffectHandler<'a>>,
+) -> Step<'a> {
+    match environment.definitions.get(&variable) {
+        Some(definition) => {
+            let path = context.path();
+            Step::new(Delta, context.plug(definition.clone()))
+                .at_redex(variable.span)
+                .within(&path)
+        }
+        None => reduce_stuck(context.plug(Var(variable)), environment),
+    }
+}
+
+enum Weak<'a> {
+    Reduced(Step<'a>),
+    /// The term is an answer `A ::= V | (λx.A)M`.
+    Answer(Term<'a>),
+    /// The term is `E[x]` where `x` is not bound by `E`.
+    Demands(Variable<'a>, EvaluationContext<'a>),
+}
+
+fn reduce_weak(term: Term<'_>) -> Weak<'_> {
+    match term {
+        Var(variable) => Weak::Demands(variable, EvaluationContext::Hole),
+        term @ Abs(_) => Weak::Answer(term),
+        App! { left: Abs! { variable, term: body }, right } => reduce_let(variable, body, right),
+        App! { left, right } => match reduce_weak(left) {
+            Weak::Reduced(step) => Weak::Reduced(step.map(&[Left], |left| app(left, right))),
+            Weak::Answer(answer) => Weak::Reduced(lift(answer, right)),
+            Weak::Demands(needed, context) => {
+                Weak::Demands(needed, EvaluationContext::App(Box::new(context), right))
+            }
+        },
+    }
+}
+
+fn reduce_let<'a>(variable: Variable<'a>, body: Term<'a>, argument: Term<'a>) -> Weak<'a> {
+    match reduce_weak(body) {
+        Weak::Reduced(step) => {
+            Weak::Reduced(step.map(&[Left, Body], |body| app(abs(variable, body), argument)))
+        }
+        Weak::Answer(answer) => Weak::Answer(app(abs(variable, answer), argument)),
+        Weak::Demands(needed, context) if needed != variable => Weak::Demands(
+            needed,
+            EvaluationContext::Rec(variable, Box::new(context), argument),
+        ),
+        Weak::Demands(_, context) => match argument {
+            value @ Abs(_) => Weak::Reduced(deref(variable, context, value)),
+            argument => reduce_demanded_argument(variable, context, argument),
+        },
+    }
+}
+
+fn reduce_demanded_argument<'a>(
+    variable: Variable<'a>,
+    context: EvaluationContext<'a>,
+    argument: Term<'a>,
+) -> Weak<'a> {
+    match reduce_weak(argument) {
+        Weak::Reduced(step) => Weak::Reduced(step.map(&[Right], |argument| {
+            app(abs(variable, context.plug(Var(variable))), argument)
+        })),
+        Weak::Answer(answer) => {
+            Weak::Reduced(assoc(abs(variable, context.plug(Var(variable))), answer))
+        }
+        Weak::Demands(needed, argument_context) => Weak::Demands(
+            needed,
+            EvaluationContext::Jit(variable, Box::new(context), Box::new(argument_context)),
+        ),
+    }
+}
+
+/// Continues below the abstraction of an answer.
+///
+/// The abstraction is floated out of the surrounding let-bindings first:
+/// `(λx.λy.M)N = λy.(λx.M)N`, which is fine since it will never be applied again.
+fn reduce_answer<'a>(
+    answer: Term<'a>,
+    environment: &mut Environment<'a, impl EffectHandler<'a>>,
+) -> Step<'a> {
+    match answer {
+        Abs! { variable, term } => {
+            reduce(term, environment).map(&[Body], |term| abs(variable, term))
+        }
+        answer => {
+            let lets = count_lets(&answer);
+            match float(answer) {
+                Modified(answer) => Step::new(Alpha, answer).at_redex(None),
+                Original(answer) => {
+                    let mut step = reduce(answer, environment);
+                    step.redex_path = step
+                        .redex_path
+                        .and_then(|path| unfloat(lets, path.directions()))
+                        .map(Path::from);
+                    step
+                }
+            }
+        }
+    }
+}
+
+fn count_lets(answer: &Term) -> usize {
+    match answer {
+        App(app) => match &app.left {
+            Abs(abs) => 1 + count_lets(&abs.term),
+            _ => 0,
+        },
+        _ => 0,
+    }
+}
+
+/// Finds where a path of the floated answer was before the abstraction was floated out
+/// of its `lets` let-bindings.
+fn unfloat(lets: usize, path: &[Direction]) -> Option<Vec<Direction>> {
+    match (lets, path) {
+        (0, path) => Some(path.to_vec()),
+        (_, []) => Some([&[Left, Body], &unfloat(lets - 1, &[])?[..]].concat()),
+        (_, [Body]) => Some(vec![]),
+        (_, [Body, Left]) => Some(vec![Left]),
+        (_, [Body, Right, rest @ ..]) => Some([&[Right], rest].concat()),
+        (_, [Body, Left, Body, rest @ ..]) => {
+            let inner = unfloat(lets - 1, &[&[Body], rest].concat())?;
+            Some([&[Left, Body], &inner[..]].concat())
+        }
+        _ => None,
+    }
+}
+
+fn float(answer: Term<'_>) -> TermResult<'_> {
+    let mut lets = Vec::new();
+    let mut answer = answer;
+    while let App! { left: Abs! { variable, term }, right } = answer {
+        lets.push((variable, right));
+        answer = term;
+    }
+    let Abs! { variable: bound, term } = answer else {
+        unreachable!("Answers are values in let-bindings")
+    };
+    // The abstraction's variable has to be fresh for all let-bindings it is floated out of,
+    // otherwise renaming it for one of them could make it clash with another one
+    let clashes = |v: &Variable| {
+        (lets.iter()).any(|(variable, argument)| v == variable || is_free_in(argument)(v))
+    };
+    let renamed = clashes(&bound);
+    let answer = match renamed {
+        true => rename_variable(bound, term, |v| !clashes(v)),
+        false => term,
+    };
+    let lets = lets.into_iter().rev();
+    let answer = lets.fold(answer, |answer, (variable, right)| {
+        app(abs(variable, answer), right)
+    });
+    match renamed {
+        true => Modified(answer),
+        false => Original(abs(bound, answer)),
+    }
+}
+
+/// Reduces a term whose evaluation got stuck on a free variable to normal form.
+///
+/// Let-bindings that are still around are substituted once their body is in normal form.
+/// Values are substituted as they are, since reducing them below their abstraction could
+/// diverge, everything else is evaluated first so that it is not duplicated.
+fn reduce_stuck<'a>(
+    term: Term<'a>,
+    environment: &mut Environment<'a, impl EffectHandler<'a>>,
+) -> Step<'a> {
+    match term {
+        App! { left: Abs! { variable, term: body }, span } if !is_free_in(&body)(&variable) => {
+            Step::new(Beta, body).at_redex(span)
+        }
+        App! { left: Abs! { variable, term: body }, right } => {
+            match reduce(body, environment).id_or_err() {
+                Ok(body) => reduce_bound_argument(variable, body, right, environment),
+                Err(body) => body.map(&[Left, Body], |body| app(abs(variable, body), right)),
+            }
+        }
+        App! { left, right, span } => match reduce(left, environment).id_or_err() {
+            Ok(left) => match reduce(right, environment).id_or_err() {
+                Ok(right) => perform(left, right, span, environment),
+                Err(right) => right.map(&[Right], |right| app(left, right).with_span(span)),
+            },
+            Err(left) => left.map(&[Left], |left| app(left, right).with_span(span)),
+        },
+        term => Step::new(Id, term),
+    }
+}
+
+fn reduce_bound_argument<'a>(
+    variable: Variable<'a>,
+    body: Term<'a>,
+    argument: Term<'a>,
+    environment: &mut Environment<'a, impl EffectHandler<'a>>,
+) -> Step<'a> {
+    let step = match reduce_weak(argument) {
+        Weak::Reduced(step) => step,
+        Weak::Answer(value @ Abs(_)) => Step::new(Id, value),
+        Weak::Answer(answer) => return assoc(abs(variable, body), answer),
+        Weak::Demands(needed, context) => reduce_demand(needed, context, environment),
+    };
+    match step.id_or_err() {
+        Ok(argument) => rename_and_substitute(Abstraction::new(variable, body), argument, None),
+        Err(argument) => argument.map(&[Right], |argument| app(abs(variable, body), argument)),
+    }
+}
+
+/// `(λx.E[x])V = (λx.E[V])V`
+fn deref<'a>(variable: Variable<'a>, context: EvaluationContext<'a>, value: Term<'a>) -> Step<'a> {
+    let function = abs(variable, context.clone().plug(Var(variable)));
+    match rename_bound(function, is_bound_in(&value)) {
+        Modified(function) => Step::new(Alpha, app(function, value)).at_redex(None),
+        Original(_) => Step::new(
+            Deref,
+            app(abs(variable, context.plug(value.clone())), value),
+        )
+        .at_redex(None),
+    }
+}
+
+/// `(λx.A)MN = (λx.AN)M`
+fn lift<'a>(answer: Term<'a>, argument: Term<'a>) -> Step<'a> {
+    match rename_bound(answer, is_bound_in(&argument)) {
+        Modified(answer) => Step::new(Alpha, app(answer, argument)).at_redex(None),
+        Original(App! { left: Abs! { variable, term: answer }, right }) => {
+            Step::new(Lift, app(abs(variable, app(answer, argument)), right)).at_redex(None)
+        }
+        Original(_) => unreachable!("Answers in function position are let-bindings"),
+    }
+}
+
+/// `(λx.E[x])((λy.A)M) = (λy.(λx.E[x])A)M`
+fn assoc<'a>(function: Term<'a>, answer: Term<'a>) -> Step<'a> {
+    match rename_bound(answer, is_bound_in(&function)) {
+        Modified(answer) => Step::new(Alpha, app(function, answer)).at_redex(None),
+        Original(App! { left: Abs! { variable, term: answer }, right }) => {
+            Step::new(Assoc, app(abs(variable, app(function, answer)), right)).at_redex(None)
+        }
+        Original(_) => unreachable!("Answers in argument position are let-bindings"),
+    }
+}
+
+/// Evaluation Contexts:
+/// ```text
+/// E ::= [ ] | EM | (λx.E[x])E | (λx.E)M
+/// ```
+#[derive(Debug, Clone)]
+enum EvaluationContext<'a> {
+    /// []
+    Hole,
+    /// EM
+    App(Box<EvaluationContext<'a>>, Term<'a>),
+    /// (λx.E[x])E
+    Jit(
+        Variable<'a>,
+        Box<EvaluationContext<'a>>,
+        Box<EvaluationContext<'a>>,
+    ),
+    /// (λx.E)M
+    Rec(Variable<'a>, Box<EvaluationContext<'a>>, Term<'a>),
+}
+
+impl<'a> EvaluationContext<'a> {
+    /// Fills the context's hole with the given term.
+    fn plug(self, term: Term<'a>) -> Term<'a> {
+        match self {
+            EvaluationContext::Hole => term,
+            EvaluationContext::App(context, right) => app(context.plug(term), right),
+            EvaluationContext::Jit(variable, body, argument) => {
+                app(abs(variable, body.plug(Var(variable))), argument.plug(term))
+            }
+            EvaluationContext::Rec(variable, body, argument) => {
+                app(abs(variable, body.plug(term)), argument)
+            }
+        }
+    }
+
+    /// The directions from the root of the plugged term to the hole.
+    fn path(&self) -> Vec<Direction> {
+        match self {
+            EvaluationContext::Hole => Vec::new(),
+            EvaluationContext::App(context, _) => [vec![Left], context.path()].concat(),
+            EvaluationContext::Jit(_, _, argument) => [vec![Right], argument.path()].concat(),
+            EvaluationContext::Rec(_, body, _) => [vec![Left, Body], body.path()].concat(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dereferences_needed_variable_bound_to_value() {
+        let step = reduce(
+            λ![(λx.x Y) (λz.z)],
+            &mut Environment::<NoSideEffects>::default(),
+        );
+        assert_eq!(Deref, step.kind);
+        assert_eq!(λ![(λx.(λz.z) Y) (λz.z)], step.term);
+    }
+
+    #[test]
+    fn only_dereferences_demanded_occurrence() {
+        let step = reduce(
+            λ![(λx.x x) (λz.z)],
+            &mut Environment::<NoSideEffects>::default(),
+        );
+        assert_eq!(Deref, step.kind);
+        assert_eq!(λ![(λx.(λz.z) x) (λz.z)], step.term);
+    }
+
+    #[test]
+    fn lifts_let_binding_out_of_function_position() {
+        let step = reduce(
+            λ![((λx.λy.y) X) Y],
+            &mut Environment::<NoSideEffects>::default(),
+        );
+        assert_eq!(Lift, step.kind);
+        assert_eq!(λ![(λx.(λy.y) Y) X], step.term);
+    }
+
+    #[test]
+    fn associates_let_binding_out_of_argument_position() {
+        let step = reduce(
+            λ![(λx.x) ((λy.λz.z) Y)],
+            &mut Environment::<NoSideEffects>::default(),
+        );
+        assert_eq!(Assoc, step.kind);
+        assert_eq!(λ![(λy.(λx.x) (λz.z)) Y], step.term);
+    }
+
+    #[test]
+    fn evaluates_needed_argument_in_place() {
+        let step = reduce(
+            λ![(λx.x)((λy.y)(λz.z))],
+            &mut Environment::<NoSideEffects>::default(),
+        );
+        assert_eq!(Deref, step.kind);
+        assert_eq!(λ![(λx.x)((λy.λz.z)(λz.z))], step.term);
+    }
+
+    #[test]
+    fn renames_bound_variables_before_lifting() {
+        let step = reduce(
+            λ![((λx.λy.y) X) x],
+            &mut Environment::<NoSideEffects>::default(),
+        );
+        assert_eq!(Alpha, step.kind);
+        assert_eq!(
+            app(app(abs(("x", 1), λ![λy.y]), var("X")), var("x")),
+            step.term
+        );
+    }
+
+    #[test]
+    fn does_not_evaluate_unneeded_arguments() {
+        let kinds: Vec<_> = evaluate_by_need(λ![(λx.Y) ((λx.x x) (λx.x x))])
+            .map(|step| step.kind)
+            .collect();
+        assert_eq!(vec![Beta], kinds);
+    }
+
+    #[test]
+    fn evaluates_shared_arguments_once() {
+        let term = λ![(λx.λf.f x x) ((λy.y) (λz.z))];
+        let by_need: Vec<_> = evaluate_by_need(term.clone()).collect();
+        let by_name: Vec<_> = evaluate(term).collect();
+        assert_eq!(by_name.last().unwrap().term, by_need.last().unwrap().term);
+        assert_eq!(3, count(Beta, &by_name));
+        assert_eq!(2, count(Beta, &by_need));
+        assert_eq!(1, count(Deref, &by_need));
+    }
+
+    #[test]
+    fn reduces_to_normal_form() {
+        let terms = [
+            λ![λx.(λy.y) x],
+            λ![X ((λy.y) Y)],
+            λ![(λt f.t) A B],
+            λ![(λn f x.f (n f x)) (λf x.f x)],
+        ];
+        for term in terms {
+            let expected = evaluate(term.clone()).last().unwrap().term;
+            let actual = evaluate_by_need(term).last().unwrap().term;
+            assert_eq!(expected, actual);
+        }
+    }
+
+    #[test]
+    fn reduces_with_naming_conflicts_in_nested_lets() {
+        let expression = λ![(λd.d x d) (λb.λd.(λd.d) (d b) (λa.λc.d))];
+        let expected = λ![λb.λd.d b (λa.λc.d)];
+        let steps: Vec<_> = evaluate_by_need(expression).take(100).collect();
+        assert!(steps.len() < 100, "{}", steps.last().unwrap().term);
+        let reduced = &steps.last().unwrap().term;
+        assert!(alpha_eq(&expected, reduced), "{reduced}");
+    }
+
+    #[test]
+    fn expands_demanded_definitions() {
+        let definitions = [(Variable::new("I"), λ![λx.x])].into_iter().collect();
+        let step = reduce(λ![I Y], &mut Environment::new(definitions, NoSideEffects));
+        assert_eq!(Delta, step.kind);
+        assert_eq!(λ![(λx.x) Y], step.term);
+    }
+
+    #[test]
+    fn records_path_of_expanded_definition() {
+        let definitions = [(Variable::new("I"), λ![λx.x])].into_iter().collect();
+        let step = reduce(
+            λ![(λz.I Y) Z],
+            &mut Environment::new(definitions, NoSideEffects),
+        );
+        let path = Path::from(vec![Left, Body, Left]);
+        assert_eq!(Some(&path), step.redex_path.as_ref());
+        assert_eq!(Some(&λ![λx.x]), path.get(&step.term));
+    }
+
+    fn count(kind: StepKind, steps: &[Step]) -> usize {
+        steps.iter().filter(|step| step.kind == kind).count()
+    }
+}
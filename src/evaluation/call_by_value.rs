@@ -0,0 +1,51 @@
+//! Call-by-value reduction to weak normal form: arguments are reduced before they are
+//! substituted, but nothing is reduced below an abstraction.
+use super::*;
+use StepKind::*;
+use Term::*;
+
+pub(crate) fn reduce<'a>(
+    term: impl Into<Term<'a>>,
+    environment: &mut Environment<'a, impl EffectHandler<'a>>,
+) -> Step<'a> {
+    match term.into() {
+        App! { left, right, span } => match reduce(left, environment).id_or_err() {
+            Ok(left) => match reduce(right, environment).id_or_err() {
+                Ok(right) => match left {
+                    Abs(abs) => rename_and_substitute(*abs, right, span),
+                    left => perform(left, right, span, environment),
+                },
+                Err(right) => right.map(&[Direction::Right], |right| {
+                    app(left, right).with_span(span)
+                }),
+            },
+            Err(left) => left.map(&[Direction::Left], |left| app(left, right).with_span(span)),
+        },
+        term @ Abs(_) => Step::new(Id, term),
+        Var(variable) => expand(variable, &environment.definitions),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reduces_argument_before_contracting_application() {
+        let expression = λ![ (λx.x) ((λy.y) Y) ];
+        let expected = λ![ (λx.x) Y ];
+        assert_eq!(
+            expected,
+            reduce(expression, &mut Environment::<NoSideEffects>::default()).term
+        );
+    }
+
+    #[test]
+    fn does_not_reduce_below_abstraction() {
+        let expression = λ![ λx.(λy.y) x ];
+        assert_eq!(
+            Id,
+            reduce(expression, &mut Environment::<NoSideEffects>::default()).kind
+        );
+    }
+}
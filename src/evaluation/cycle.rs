@@ -0,0 +1,95 @@
+use super::*;
+
+/// Notices when an evaluation comes back to a term (up to α-equivalence) with Brent's
+/// algorithm, so that it keeps a single earlier term no matter how long the evaluation runs.
+///
+/// The kept term is replaced by the current one whenever the number of terms visited since
+/// it reaches the next power of two. Once the evaluation is inside a cycle, so is the kept
+/// term, and it comes around again after exactly one cycle length. This happens at most
+/// about twice as many steps after the cycle is entered as the cycle is long, but the
+/// reported start of the cycle may be later than the first term that repeats.
+#[derive(Debug, Default)]
+pub(crate) struct CycleDetector<'a> {
+    /// The kept term and the step after which it was visited.
+    kept: Option<(usize, Term<'a>)>,
+    /// The number of terms visited since the kept one.
+    visited: usize,
+    /// The number of terms that may be visited before the kept one is replaced.
+    power: usize,
+}
+
+impl<'a> CycleDetector<'a> {
+    /// Records the term after `step` steps and returns the step of an earlier visit of the
+    /// same term, if it is the kept one.
+    pub(crate) fn visit(&mut self, step: usize, term: &Term<'a>) -> Option<usize> {
+        if let Some((kept_step, kept)) = &self.kept {
+            if alpha_eq(kept, term) {
+                return Some(*kept_step);
+            }
+            self.visited += 1;
+            if self.visited < self.power {
+                return None;
+            }
+        }
+        self.kept = Some((step, term.clone()));
+        self.visited = 0;
+        self.power = (2 * self.power).max(1);
+        None
+    }
+
+    /// Forgets the terms seen so far, e.g. because a side effect was performed.
+    pub(crate) fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_alpha_equivalent_term() {
+        let mut detector = CycleDetector::default();
+        assert_eq!(None, detector.visit(0, &λ![(λx.x x) (λx.x x)]));
+        assert_eq!(Some(0), detector.visit(1, &λ![(λy.y y) (λz.z z)]));
+    }
+
+    #[test]
+    fn detects_cycles_after_their_start() {
+        let terms = [λ![A], λ![B], λ![C], λ![D], λ![B], λ![C], λ![D], λ![B]];
+        let mut detector = CycleDetector::default();
+        let detected = (terms.iter().enumerate())
+            .find_map(|(step, term)| Some((step, detector.visit(step, term)?)));
+        let (step, start) = detected.unwrap();
+        assert_eq!(3, step - start);
+        assert!(alpha_eq(&terms[start], &terms[step]));
+    }
+
+    #[test]
+    fn forgets_terms_when_cleared() {
+        let mut detector = CycleDetector::default();
+        assert_eq!(None, detector.visit(0, &λ![λx.x]));
+        detector.clear();
+        assert_eq!(None, detector.visit(1, &λ![λx.x]));
+        assert_eq!(Some(1), detector.visit(2, &λ![λy.y]));
+    }
+
+    #[test]
+    fn keeps_one_term_during_long_evaluations() {
+        let layers = 200;
+        let body = (0..layers).fold(λ![x x], |body, _| app(abs("y", body), var("z")));
+        let term = app(abs("x", body.clone()), abs("x", body));
+        let mut detector = CycleDetector::default();
+        let mut largest = 0;
+        let mut cycles = Vec::new();
+        for (step, Step { term, .. }) in evaluate(term).enumerate().take(3_000) {
+            largest = largest.max(term.size());
+            if let Some(start) = detector.visit(step, &term) {
+                cycles.push(step - start);
+            }
+            let kept = detector.kept.as_ref().map_or(0, |(_, kept)| kept.size());
+            assert!(kept <= largest, "{kept} > {largest} after {step} steps");
+        }
+        assert_eq!(Some(&(layers + 1)), cycles.first());
+    }
+}
@@ -0,0 +1,358 @@
+use super::*;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::iter;
+use DeBruijnTerm::*;
+use StepKind::*;
+
+/// A nameless representation of a [`Term`] where bound variables refer to their
+/// abstraction by the number of abstractions in between.
+/// Substitution never needs to rename anything in this representation.
+///
+/// Abstractions remember the name of their variable so that the term can be converted
+/// back to a [`Term`] with the original names.
+/// These names are ignored when comparing terms, so two terms are equal iff they are α-equivalent.
+///
+/// Example: `λx.λy.x y z` is `λ.λ.1 0 z`.
+#[derive(Debug, Clone, Eq)]
+pub enum DeBruijnTerm<'a> {
+    /// A variable bound by the abstraction `index` abstractions further out (`0` is the innermost).
+    Bound(usize),
+    Free(Variable<'a>),
+    Abs(Variable<'a>, Box<DeBruijnTerm<'a>>),
+    App(Box<DeBruijnTerm<'a>>, Box<DeBruijnTerm<'a>>),
+}
+
+impl<'a> PartialEq for DeBruijnTerm<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Bound(left), Bound(right)) => left == right,
+            (Free(left), Free(right)) => left == right,
+            (Abs(_, left), Abs(_, right)) => left == right,
+            (App(left, left_argument), App(right, right_argument)) => {
+                left == right && left_argument == right_argument
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<'a> Hash for DeBruijnTerm<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Bound(index) => index.hash(state),
+            Free(variable) => variable.hash(state),
+            Abs(_, term) => term.hash(state),
+            App(left, right) => {
+                left.hash(state);
+                right.hash(state);
+            }
+        }
+    }
+}
+
+impl<'a> From<&Term<'a>> for DeBruijnTerm<'a> {
+    fn from(term: &Term<'a>) -> Self {
+        to_de_bruijn(term, &mut Vec::new())
+    }
+}
+
+impl<'a> From<Term<'a>> for DeBruijnTerm<'a> {
+    fn from(term: Term<'a>) -> Self {
+        (&term).into()
+    }
+}
+
+fn to_de_bruijn<'a>(term: &Term<'a>, binders: &mut Vec<Variable<'a>>) -> DeBruijnTerm<'a> {
+    match term {
+        Term::Var(variable) if variable.disambiguator == Disambiguator::Symbol => Free(*variable),
+        Term::Var(variable) => match binders.iter().rev().position(|b| b == variable) {
+            Some(index) => Bound(index),
+            None => Free(*variable),
+        },
+        Abs! { variable, term } => {
+            binders.push(*variable);
+            let term = to_de_bruijn(term, binders);
+            binders.pop();
+            Abs(*variable, Box::new(term))
+        }
+        App! { left, right } => App(
+            Box::new(to_de_bruijn(left, binders)),
+            Box::new(to_de_bruijn(right, binders)),
+        ),
+    }
+}
+
+impl<'a> From<&DeBruijnTerm<'a>> for Term<'a> {
+    /// Abstractions keep their original variable unless that would capture a variable
+    /// in their body, in which case the smallest disambiguator that doesn't is used.
+    fn from(term: &DeBruijnTerm<'a>) -> Self {
+        let mut free = HashSet::new();
+        find_referenced(term, 0, &[], &mut free);
+        to_named(term, &free, &mut Vec::new())
+    }
+}
+
+impl<'a> From<DeBruijnTerm<'a>> for Term<'a> {
+    fn from(term: DeBruijnTerm<'a>) -> Self {
+        (&term).into()
+    }
+}
+
+fn to_named<'a>(
+    term: &DeBruijnTerm<'a>,
+    free: &HashSet<Variable<'a>>,
+    binders: &mut Vec<Variable<'a>>,
+) -> Term<'a> {
+    match term {
+        Bound(index) => Term::Var(bound_variable(*index, binders)),
+        Free(variable) => Term::Var(*variable),
+        Abs(variable, term) => {
+            let variable = name_binder(*variable, term, free, binders);
+            binders.push(variable);
+            let term = to_named(term, free, binders);
+            binders.pop();
+            abs(variable, term)
+        }
+        App(left, right) => app(
+            to_named(left, free, binders),
+            to_named(right, free, binders),
+        ),
+    }
+}
+
+fn name_binder<'a>(
+    variable: Variable<'a>,
+    term: &DeBruijnTerm<'a>,
+    free: &HashSet<Variable<'a>>,
+    binders: &[Variable<'a>],
+) -> Variable<'a> {
+    // Only variables that are free somewhere or bound further out can be captured.
+    if !free.contains(&variable) && !binders.contains(&variable) {
+        return variable;
+    }
+    let mut referenced = HashSet::new();
+    find_referenced(term, 1, binders, &mut referenced);
+    iter::once(variable)
+        .chain(iter::once(Variable::new(variable.name)))
+        .chain((1..).map(|d| Variable::new_with(variable.name, d)))
+        .find(|v| !referenced.contains(v))
+        .unwrap()
+}
+
+/// The variable bound by the abstraction `index` abstractions further out.
+///
+/// Indices that go beyond all abstractions only occur in open terms, they stand for the free
+/// variables `_₀`, `_₁`, … counted from the innermost one outside of the term.
+fn bound_variable<'a>(index: usize, binders: &[Variable<'a>]) -> Variable<'a> {
+    match binders.len().checked_sub(index + 1) {
+        Some(position) => binders[position],
+        None => Variable::new_with("_", (index - binders.len()) as u64),
+    }
+}
+
+/// Finds the variables outside of the term's `depth` innermost abstractions that it refers to.
+fn find_referenced<'a>(
+    term: &DeBruijnTerm<'a>,
+    depth: usize,
+    binders: &[Variable<'a>],
+    referenced: &mut HashSet<Variable<'a>>,
+) {
+    match term {
+        Bound(index) if *index >= depth => {
+            referenced.insert(bound_variable(index - depth, binders));
+        }
+        Bound(_) => {}
+        Free(variable) => {
+            referenced.insert(*variable);
+        }
+        Abs(_, term) => find_referenced(term, depth + 1, binders, referenced),
+        App(left, right) => {
+            find_referenced(left, depth, binders, referenced);
+            find_referenced(right, depth, binders, referenced);
+        }
+    }
+}
+
+impl<'a> fmt::Display for DeBruijnTerm<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bound(index) => write!(f, "{index}"),
+            Free(variable) => variable.fmt(f),
+            Abs(_, term) => write!(f, "λ.{term}"),
+            App(left, right) => {
+                with_parenthesis(matches!(**left, Abs(..)), f, |f| left.fmt(f))?;
+                f.write_char(' ')?;
+                with_parenthesis(matches!(**right, App(..) | Abs(..)), f, |f| right.fmt(f))
+            }
+        }
+    }
+}
+
+/// Evaluates a program's term in normal order on its [`DeBruijnTerm`] representation.
+/// This yields the same β- and δ-steps as [`evaluate_program`], but never needs an α-conversion.
+pub fn evaluate_de_bruijn<'a>(program: impl Into<Program<'a>>) -> impl Iterator<Item = Step<'a>> {
+    evaluate_nameless(program).map(|(kind, term)| Step::new(kind, Term::from(term)))
+}
+
+/// Like [`evaluate_de_bruijn`], but yields the kind and the [`DeBruijnTerm`] of each step,
+/// so that only the terms that are needed (e.g. the normal form) are converted to a [`Term`].
+pub fn evaluate_nameless<'a>(
+    program: impl Into<Program<'a>>,
+) -> impl Iterator<Item = (StepKind, DeBruijnTerm<'a>)> {
+    let Program { definitions, term } = program.into();
+    let definitions: HashMap<_, _> = definitions
+        .iter()
+        .map(|(variable, term)| (*variable, DeBruijnTerm::from(term)))
+        .collect();
+    iter::successors(Some((Id, DeBruijnTerm::from(term))), move |(_, term)| {
+        let (kind, term) = reduce(term.clone(), &definitions);
+        (kind != Id).then_some((kind, term))
+    })
+    .skip(1)
+}
+
+fn reduce<'a>(
+    term: DeBruijnTerm<'a>,
+    definitions: &HashMap<Variable<'a>, DeBruijnTerm<'a>>,
+) -> (StepKind, DeBruijnTerm<'a>) {
+    match term {
+        App(box Abs(_, body), argument) => (Beta, instantiate(*body, &argument, 0)),
+        App(left, right) => match reduce(*left, definitions) {
+            (Id, left) => {
+                let (kind, right) = reduce(*right, definitions);
+                (kind, App(Box::new(left), Box::new(right)))
+            }
+            (kind, left) => (kind, App(Box::new(left), right)),
+        },
+        Abs(variable, term) => {
+            let (kind, term) = reduce(*term, definitions);
+            (kind, Abs(variable, Box::new(term)))
+        }
+        Free(variable) => match definitions.get(&variable) {
+            Some(definition) => (Delta, definition.clone()),
+            None => (Id, Free(variable)),
+        },
+        term @ Bound(_) => (Id, term),
+    }
+}
+
+/// Replaces the variable bound `depth` abstractions further out with `argument`
+/// and removes that abstraction.
+fn instantiate<'a>(
+    term: DeBruijnTerm<'a>,
+    argument: &DeBruijnTerm<'a>,
+    depth: usize,
+) -> DeBruijnTerm<'a> {
+    match term {
+        Bound(index) if index == depth => shift(argument.clone(), depth, 0),
+        Bound(index) if index > depth => Bound(index - 1),
+        term @ (Bound(_) | Free(_)) => term,
+        Abs(variable, term) => Abs(variable, Box::new(instantiate(*term, argument, depth + 1))),
+        App(left, right) => App(
+            Box::new(instantiate(*left, argument, depth)),
+            Box::new(instantiate(*right, argument, depth)),
+        ),
+    }
+}
+
+/// Adjusts the indices of variables bound outside of `term` after it was moved below `by` abstractions.
+fn shift(term: DeBruijnTerm<'_>, by: usize, cutoff: usize) -> DeBruijnTerm<'_> {
+    match term {
+        _ if by == 0 => term,
+        Bound(index) if index >= cutoff => Bound(index + by),
+        term @ (Bound(_) | Free(_)) => term,
+        Abs(variable, term) => Abs(variable, Box::new(shift(*term, by, cutoff + 1))),
+        App(left, right) => App(
+            Box::new(shift(*left, by, cutoff)),
+            Box::new(shift(*right, by, cutoff)),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refers_to_bound_variables_by_index() {
+        let term = DeBruijnTerm::from(λ![λx.λy.x y z]);
+        assert_eq!("λ.λ.1 0 z", term.to_string());
+    }
+
+    #[test]
+    fn refers_to_innermost_binder_of_shadowed_variable() {
+        let term = DeBruijnTerm::from(λ![λx.λx.x]);
+        assert_eq!("λ.λ.0", term.to_string());
+    }
+
+    #[test]
+    fn symbols_are_always_free() {
+        let term = DeBruijnTerm::from(abs(Variable::new_with("x", Disambiguator::Symbol), λ![:x]));
+        assert_eq!("λ.:x", term.to_string());
+    }
+
+    #[test]
+    fn converts_back_to_original_term() {
+        let terms = [
+            λ![λx.λy.x y z],
+            λ![λx.λx.x],
+            λ![(λf.λx.f (f x)) (λy.y) Y],
+            abs(("x", 1), abs("x", app(var_with("x", 1), var("x")))),
+        ];
+        for term in terms {
+            assert_eq!(term, Term::from(DeBruijnTerm::from(&term)));
+        }
+    }
+
+    #[test]
+    fn converts_indices_beyond_all_abstractions_to_free_variables() {
+        let x = Variable::new("x");
+        let term = Abs(x, Box::new(App(Box::new(Bound(0)), Box::new(Bound(2)))));
+        assert_eq!(abs(x, app(x, var_with("_", 1))), Term::from(term));
+        let shadowing = Abs(Variable::new_with("_", 0), Box::new(Bound(1)));
+        assert_eq!(abs("_", var_with("_", 0)), Term::from(shadowing));
+    }
+
+    #[test]
+    fn ignores_names_when_comparing() {
+        assert_eq!(DeBruijnTerm::from(λ![λx.x]), DeBruijnTerm::from(λ![λy.y]));
+        assert_ne!(DeBruijnTerm::from(λ![λx.y]), DeBruijnTerm::from(λ![λy.x]));
+    }
+
+    #[test]
+    fn uses_smallest_disambiguator_that_avoids_capture() {
+        let steps: Vec<_> = evaluate_de_bruijn(λ![(λy.λx.y) x]).collect();
+        assert_eq!(vec![Step::new(Beta, abs(("x", 1), var("x")))], steps);
+    }
+
+    #[test]
+    fn shifts_free_indices_of_argument_below_abstractions() {
+        let steps: Vec<_> = evaluate_de_bruijn(λ![λz.(λy.λx.y) z]).collect();
+        assert_eq!(λ![λz.λx.z], steps[0].term);
+    }
+
+    #[test]
+    fn takes_same_steps_as_normal_order_without_alpha_conversions() {
+        let term = λ![(λf.λx.f (f x)) (λf.λx.f (f x)) (λy.λx.y) x];
+        let expected: Vec<_> = evaluate(term.clone())
+            .filter(|step| step.kind != Alpha)
+            .map(|step| DeBruijnTerm::from(step.term))
+            .collect();
+        let actual: Vec<_> = evaluate_de_bruijn(term)
+            .map(|step| DeBruijnTerm::from(step.term))
+            .collect();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn yields_same_steps_without_names() {
+        let term = λ![(λf.λx.f (f x)) (λy.λx.y) x];
+        let expected: Vec<_> = evaluate_de_bruijn(term.clone()).collect();
+        let actual: Vec<_> = evaluate_nameless(term)
+            .map(|(kind, term)| Step::new(kind, term.into()))
+            .collect();
+        assert_eq!(expected, actual);
+    }
+}
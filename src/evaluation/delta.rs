@@ -0,0 +1,257 @@
+use super::*;
+use crate::syntax;
+use std::collections::HashMap;
+use StepKind::*;
+use Term::*;
+
+/// Named terms that free variables are expanded to during δ-reduction.
+///
+/// A definition can only use the definitions before it. When a name is defined again,
+/// the earlier definition is kept under a renamed variable that the definitions in between
+/// (and the new one) refer to, which is how a file redefines what it imports, and
+/// [`lint`](crate::lint) warns about. Uses of a definition in itself or in earlier
+/// definitions are renamed too, so that they stay free (see
+/// [`check_definitions`](crate::check_definitions)).
+///
+/// Bound variables that share their name with a definition or with a free variable of one
+/// are renamed on construction so that the definitions can later be expanded without
+/// capturing anything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Definitions<'a> {
+    terms: HashMap<Variable<'a>, Term<'a>>,
+    free: Variables<'a>,
+}
+
+impl<'a> Definitions<'a> {
+    pub fn get(&self, variable: &Variable<'a>) -> Option<&Term<'a>> {
+        self.terms.get(variable)
+    }
+
+    pub fn contains(&self, variable: &Variable<'a>) -> bool {
+        self.terms.contains_key(variable)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Variable<'a>, &Term<'a>)> {
+        self.terms.iter()
+    }
+
+    /// Renames all bound variables in the given term that would shadow a definition
+    /// or capture a free variable of one when it is expanded.
+    pub fn unshadow(&self, term: Term<'a>) -> TermResult<'a> {
+        rename_bound(term, |v| !self.contains(v) && !self.free.contains(v))
+    }
+}
+
+impl<'a> FromIterator<(Variable<'a>, Term<'a>)> for Definitions<'a> {
+    fn from_iter<T: IntoIterator<Item = (Variable<'a>, Term<'a>)>>(iter: T) -> Self {
+        let definitions: Vec<_> = iter.into_iter().collect();
+        let mut taken = Variables::new();
+        for (variable, term) in &definitions {
+            taken.insert(*variable);
+            insert_variables(term, &mut taken);
+        }
+        let mut fresh = |variable| {
+            let fresh = new_variable(variable, |v| !taken.contains(v));
+            taken.insert(fresh);
+            fresh
+        };
+        // What the names refer to at the definition that is resolved
+        let mut scope = HashMap::new();
+        let mut terms = HashMap::new();
+        for (index, (variable, term)) in definitions.iter().enumerate() {
+            let mut term = term.clone();
+            for free in free_variables(&term) {
+                let resolved = match scope.get(&free) {
+                    Some(resolved) => *resolved,
+                    None if definitions[index..].iter().any(|(v, _)| *v == free) => fresh(free),
+                    None => continue,
+                };
+                term = rename(free, resolved, term);
+            }
+            let redefined = definitions[index + 1..].iter().any(|(v, _)| v == variable);
+            let key = if redefined {
+                fresh(*variable)
+            } else {
+                *variable
+            };
+            scope.insert(*variable, key);
+            terms.insert(key, term);
+        }
+        let free = terms.values().flat_map(free_variables).collect();
+        let shadowed = Definitions { terms, free };
+        let terms = (shadowed.terms.iter())
+            .map(|(variable, term)| (*variable, shadowed.unshadow(term.clone()).term()))
+            .collect();
+        Definitions { terms, ..shadowed }
+    }
+}
+
+impl<'a> From<Vec<syntax::NominalDefinition<'a>>> for Definitions<'a> {
+    fn from(value: Vec<syntax::NominalDefinition<'a>>) -> Self {
+        value
+            .into_iter()
+            .map(|d| (d.name.into(), d.formula.into()))
+            .collect()
+    }
+}
+
+/// Expands a free variable to its definition.
+pub(crate) fn expand<'a>(variable: Variable<'a>, definitions: &Definitions<'a>) -> Step<'a> {
+    match definitions.get(&variable) {
+        Some(term) => Step::new(Delta, term.clone()).at_redex(variable.span),
+        None => Step::new(Id, Var(variable)),
+    }
+}
+
+/// Adds every variable of `term` to `variables`, bound or free.
+fn insert_variables<'a>(term: &Term<'a>, variables: &mut Variables<'a>) {
+    match term {
+        Var(variable) => {
+            variables.insert(*variable);
+        }
+        Abs! { variable, term } => {
+            variables.insert(*variable);
+            insert_variables(term, variables);
+        }
+        App! { left, right } => {
+            insert_variables(left, variables);
+            insert_variables(right, variables);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_definition_in_head_position() {
+        let definitions = definitions([("I", λ![λx.x])]);
+        let step = reduce(λ![I Y], &mut environment(definitions));
+        assert_eq!(Delta, step.kind);
+        assert_eq!(λ![(λx.x) Y], step.term);
+    }
+
+    #[test]
+    fn records_span_of_expanded_variable() {
+        let definitions = definitions([("I", λ![λx.x])]);
+        let step = reduce(parse_term("Y I"), &mut environment(definitions));
+        assert_eq!(Some(Span::from(2..3)), step.redex);
+    }
+
+    #[test]
+    fn does_not_expand_unknown_variables() {
+        let definitions = definitions([("I", λ![λx.x])]);
+        let step = reduce(λ![X Y], &mut environment(definitions));
+        assert_eq!(Id, step.kind);
+    }
+
+    #[test]
+    fn does_not_expand_discarded_arguments() {
+        let definitions = definitions([("Omega", λ![(λx.x x) (λx.x x)])]);
+        let kinds: Vec<_> = evaluate_program(Program::new(definitions, λ![(λx.Y) Omega]))
+            .map(|step| step.kind)
+            .collect();
+        assert_eq!(vec![Beta], kinds);
+    }
+
+    #[test]
+    fn expands_arguments_once_head_is_in_normal_form() {
+        let definitions = definitions([("I", λ![λx.x])]);
+        let step = reduce(λ![X I], &mut environment(definitions));
+        assert_eq!(Delta, step.kind);
+        assert_eq!(λ![X(λx.x)], step.term);
+    }
+
+    #[test]
+    fn definitions_can_refer_to_other_definitions() {
+        let definitions = definitions([("I", λ![λx.x]), ("K", λ![λx y.I x])]);
+        let normal_form = evaluate_program(Program::new(definitions, λ![K A B]))
+            .last()
+            .unwrap();
+        assert_eq!(λ![A], normal_form.term);
+    }
+
+    #[test]
+    fn does_not_expand_bound_variables_with_the_name_of_a_definition() {
+        let definitions = definitions([("I", λ![λx.x])]);
+        let steps: Vec<_> = evaluate_program(Program::new(definitions, λ![λI.I])).collect();
+        assert_eq!(1, steps.len());
+        assert_eq!(Alpha, steps[0].kind);
+        assert_eq!(abs(("I", 1), var_with("I", 1)), steps[0].term);
+    }
+
+    #[test]
+    fn renames_bound_variables_with_the_name_of_a_definition_in_definitions() {
+        let definitions = definitions([("I", λ![λx.x]), ("K", λ![λI y.I])]);
+        assert_eq!(
+            Some(&abs(("I", 1), abs("y", var_with("I", 1)))),
+            definitions.get(&Variable::new("K"))
+        );
+    }
+
+    #[test]
+    fn does_not_capture_free_variables_of_definitions() {
+        let definitions = definitions([("F", λ![z])]);
+        let normal_form = evaluate_program(Program::new(definitions, λ![λz.F]))
+            .last()
+            .unwrap();
+        assert_eq!(abs(("z", 1), var("z")), normal_form.term);
+    }
+
+    #[test]
+    fn renames_bound_variables_that_would_capture_in_definitions() {
+        let definitions = definitions([("F", λ![z]), ("K", λ![λz.F])]);
+        assert_eq!(
+            Some(&abs(("z", 1), var("F"))),
+            definitions.get(&Variable::new("K"))
+        );
+    }
+
+    #[test]
+    fn later_definitions_replace_earlier_ones() {
+        let definitions = definitions([("I", λ![λx.x]), ("I", λ![λy.y])]);
+        assert_eq!(Some(&λ![λy.y]), definitions.get(&Variable::new("I")));
+    }
+
+    #[test]
+    fn redefinitions_can_use_the_previous_definition() {
+        let definitions = definitions([
+            ("not", λ![λb.b F T]),
+            ("twice", λ![λb.not (not b)]),
+            ("not", λ![λb.not (not b)]),
+        ]);
+        assert_eq!(
+            definitions.get(&Variable::new("twice")),
+            definitions.get(&Variable::new("not"))
+        );
+        let normal_form = evaluate_program(Program::new(definitions, λ![not A]))
+            .take(100)
+            .last()
+            .unwrap();
+        assert_eq!(λ![A F T F T], normal_form.term);
+    }
+
+    #[test]
+    fn definitions_do_not_see_later_definitions() {
+        let definitions = definitions([("f", λ![λx.f x]), ("K", λ![λx.I]), ("I", λ![λx.x])]);
+        let normal_form = evaluate_program(Program::new(definitions, λ![f (K A)]))
+            .take(100)
+            .last()
+            .unwrap();
+        assert_eq!(app(var_with("f", 1), var_with("I", 1)), normal_form.term);
+    }
+
+    fn environment(definitions: Definitions) -> Environment<NoSideEffects> {
+        Environment::new(definitions, NoSideEffects)
+    }
+
+    fn definitions<'a>(
+        definitions: impl IntoIterator<Item = (&'a str, Term<'a>)>,
+    ) -> Definitions<'a> {
+        definitions
+            .into_iter()
+            .map(|(name, term)| (Variable::new(name), term))
+            .collect()
+    }
+}
@@ -1,5 +1,5 @@
 use super::Disambiguator;
-use crate::evaluation::{abs, app, Term, Variable};
+use crate::evaluation::{abs, app, decode_list, Term, Variable};
 use std::fmt;
 
 /// Encodes a value as a λ-[`Term`].
@@ -48,38 +48,87 @@ impl<'a, T: Encode<'a>, E: Encode<'a>> Encode<'a> for Result<T, E> {
 
 /// A dynamically decodable value using "type hints".
 ///
-/// e.g. `:n (λf x . f x)` is decoded as `Value::Integer(1)`.
+/// e.g. `:n (λf x . f x)` is decoded as `Value::Integer(1)`, `:s [104, 105]` as `Value::String("hi")`
+/// and `:l :b [True]` as `Value::List(vec![Value::Bool(true)])`. The hint of a list is followed by the
+/// hint of its items.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Value {
     Integer(u64),
     Bool(bool),
+    String(String),
+    List(Vec<Value>),
 }
 
 impl Decode<'_> for Value {
     fn decode(term: &Term<'_>) -> Option<Self> {
         if let Term::App(app) = term {
-            if let Term::Var(Variable {
-                name: hint,
-                disambiguator: Disambiguator::Symbol,
-            }) = &app.left
-            {
-                return match *hint {
-                    "n" => Some(Value::Integer(app.right.decode()?)),
-                    "b" => Some(Value::Bool(app.right.decode()?)),
-                    _ => None,
-                };
+            let mut hints = Vec::new();
+            let mut hint = &app.left;
+            while let Term::App(app) = hint {
+                hints.push(&app.right);
+                hint = &app.left;
             }
+            hints.push(hint);
+            hints.reverse();
+            let hints = hints
+                .into_iter()
+                .map(|hint| match hint {
+                    Term::Var(Variable {
+                        name,
+                        disambiguator: Disambiguator::Symbol,
+                        ..
+                    }) => Some(*name),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+            return decode_hinted(&hints, &app.right);
         }
 
         None
     }
 }
 
+fn decode_hinted(hints: &[&str], term: &Term<'_>) -> Option<Value> {
+    match hints {
+        ["n"] => Some(Value::Integer(term.decode()?)),
+        ["b"] => Some(Value::Bool(term.decode()?)),
+        ["s"] => Some(Value::String(term.decode()?)),
+        ["l", hints @ ..] => decode_list(term)?
+            .into_iter()
+            .map(|item| decode_hinted(hints, item))
+            .collect::<Option<_>>()
+            .map(Value::List),
+        _ => None,
+    }
+}
+
+/// Where a chain of hints stops making sense to [`Value::decode`], which is the length
+/// of the chain if a hint is missing at the end, e.g. after `:l`.
+pub(crate) fn unknown_hint(hints: &[&str]) -> Option<usize> {
+    match hints {
+        ["n" | "b" | "s"] => None,
+        ["l", hints @ ..] => unknown_hint(hints).map(|index| index + 1),
+        ["n" | "b" | "s", ..] => Some(1),
+        _ => Some(0),
+    }
+}
+
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Value::Integer(n) => n.fmt(f),
             Value::Bool(b) => b.fmt(f),
+            Value::String(s) => write!(f, "{s:?}"),
+            Value::List(items) => {
+                f.write_str("[")?;
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_str("]")
+            }
         }
     }
 }
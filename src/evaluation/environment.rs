@@ -0,0 +1,17 @@
+use super::*;
+
+/// Everything besides the term itself that a reduction step may depend on.
+#[derive(Debug, Default)]
+pub(crate) struct Environment<'a, E> {
+    pub(crate) definitions: Definitions<'a>,
+    pub(crate) effects: E,
+}
+
+impl<'a, E: EffectHandler<'a>> Environment<'a, E> {
+    pub(crate) fn new(definitions: Definitions<'a>, effects: E) -> Self {
+        Environment {
+            definitions,
+            effects,
+        }
+    }
+}
@@ -3,13 +3,39 @@ use std::iter;
 use StepKind::*;
 
 pub fn evaluate<'a>(term: impl Into<Term<'a>>) -> impl Iterator<Item = Step<'a>> {
-    iter::successors(seed_step(term.into()), step).skip(1)
+    evaluate_program(term.into())
 }
 
-fn seed_step(term: Term<'_>) -> Option<Step<'_>> {
-    Some(Step::new(Id, term))
+/// Evaluates a program's term in normal order, expanding the program's definitions as they're needed.
+/// Use [`EvaluationStrategy::evaluate`] to evaluate using a different strategy.
+pub fn evaluate_program<'a>(program: impl Into<Program<'a>>) -> impl Iterator<Item = Step<'a>> {
+    EvaluationStrategy::NormalOrder.evaluate(program)
 }
 
-fn step<'a>(previous: &Step<'a>) -> Option<Step<'a>> {
-    reduce(previous.term.clone()).not_id()
+pub(crate) fn evaluate_in<'a, E: EffectHandler<'a>>(
+    Program { definitions, term }: Program<'a>,
+    effects: E,
+    mut reduce: impl FnMut(Term<'a>, &mut Environment<'a, E>) -> Step<'a>,
+) -> impl Iterator<Item = Step<'a>> {
+    let mut seed = Some(seed_step(&definitions, term));
+    let mut environment = Environment::new(definitions, effects);
+    // Unlike `iter::successors`, only reduces when the next step is asked for,
+    // so that no side effects are performed for a step that is never taken.
+    let mut previous = None;
+    iter::from_fn(move || {
+        let step = match previous.take() {
+            Some(term) => reduce(term, &mut environment).not_id()?,
+            None => seed.take()?,
+        };
+        previous = Some(step.term.clone());
+        Some(step)
+    })
+    .skip_while(|step| step.kind == Id)
+}
+
+fn seed_step<'a>(definitions: &Definitions<'a>, term: Term<'a>) -> Step<'a> {
+    match definitions.unshadow(term) {
+        TermResult::Modified(term) => Step::new(Alpha, term),
+        TermResult::Original(term) => Step::new(Id, term),
+    }
 }
@@ -6,7 +6,7 @@ use Term::*;
 pub type Variables<'a> = HashSet<Variable<'a>>;
 
 /// Finds the free variables of a given term. Variables are free if they're not bound by an abstraction.
-pub fn free_variables<'a>(term: &'a Term) -> Variables<'a> {
+pub fn free_variables<'a>(term: &Term<'a>) -> Variables<'a> {
     let (mut bound, mut free) = default();
     find_free_variables(term, &mut bound, &mut free);
     free
@@ -17,7 +17,7 @@ pub fn is_free_in<'a>(term: &'a Term) -> impl Fn(&Variable) -> bool + Clone + 'a
     move |variable| free.contains(variable)
 }
 
-fn find_free_variables<'a>(term: &'a Term, bound: &mut Variables<'a>, free: &mut Variables<'a>) {
+fn find_free_variables<'a>(term: &Term<'a>, bound: &mut Variables<'a>, free: &mut Variables<'a>) {
     match term {
         Var(variable) => {
             if variable.disambiguator == Disambiguator::Symbol || !bound.contains(variable) {
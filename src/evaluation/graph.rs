@@ -0,0 +1,362 @@
+//! Normal order reduction on a graph instead of a tree.
+//!
+//! Nodes live in an arena and refer to each other by index, so a β-reduction can substitute
+//! a reference to the argument instead of copying it. The argument is then shared between all
+//! occurrences of the bound variable and reducing it once reduces it everywhere.
+//! Reduced applications are overwritten in place with an indirection to their contractum.
+use super::*;
+use std::collections::HashMap;
+use std::iter;
+use StepKind::*;
+
+type NodeId = usize;
+
+#[derive(Debug, Clone, Copy)]
+enum Node<'a> {
+    Free(Variable<'a>),
+    /// A variable bound by the abstraction node with the given id.
+    Bound(NodeId),
+    Abs(Variable<'a>, NodeId),
+    App(NodeId, NodeId),
+    /// A reduced node that was replaced by another node.
+    Ind(NodeId),
+}
+
+/// The state of copying the body of an abstraction during a β-reduction.
+struct Copies {
+    binder: NodeId,
+    argument: NodeId,
+    /// Copies of nodes that were already made, including abstractions that are being copied.
+    nodes: HashMap<NodeId, NodeId>,
+}
+
+/// A term represented as a graph of shared nodes together with the definitions that
+/// may be expanded while reducing it.
+#[derive(Debug, Clone)]
+pub struct Graph<'a> {
+    nodes: Vec<Node<'a>>,
+    /// Nodes whose subgraph is known to be in normal form.
+    normal: Vec<bool>,
+    root: NodeId,
+    definitions: Definitions<'a>,
+    expanded: HashMap<Variable<'a>, NodeId>,
+    live: usize,
+}
+
+impl<'a> Graph<'a> {
+    pub fn new(program: impl Into<Program<'a>>) -> Self {
+        let Program { definitions, term } = program.into();
+        let mut graph = Graph {
+            nodes: Vec::new(),
+            normal: Vec::new(),
+            root: 0,
+            definitions,
+            expanded: HashMap::new(),
+            live: 0,
+        };
+        graph.root = graph.insert(&term, &mut Vec::new());
+        graph.live = graph.nodes.len();
+        graph
+    }
+
+    /// Contracts the leftmost outermost redex in place.
+    /// Returns [`StepKind::Id`] if the graph is in normal form.
+    pub fn reduce(&mut self) -> StepKind {
+        let kind = self.reduce_node(self.root);
+        if self.nodes.len() > 2 * self.live.max(1024) {
+            self.collect_garbage();
+        }
+        kind
+    }
+
+    /// Reads back the (unshared) term that this graph represents.
+    pub fn to_term(&self) -> Term<'a> {
+        Term::from(self.to_de_bruijn(self.root, &mut Vec::new()))
+    }
+
+    fn reduce_node(&mut self, id: NodeId) -> StepKind {
+        let id = self.follow(id);
+        if self.normal[id] {
+            return Id;
+        }
+        let kind = match self.nodes[id] {
+            Node::App(function, argument) => {
+                let function = self.follow(function);
+                match self.nodes[function] {
+                    Node::Abs(_, body) => {
+                        let contractum = self.instantiate(body, function, argument);
+                        self.nodes[id] = Node::Ind(contractum);
+                        Beta
+                    }
+                    _ => match self.reduce_node(function) {
+                        Id => self.reduce_node(argument),
+                        kind => kind,
+                    },
+                }
+            }
+            Node::Abs(_, body) => self.reduce_node(body),
+            Node::Free(variable) => match self.expand(variable) {
+                Some(definition) => {
+                    self.nodes[id] = Node::Ind(definition);
+                    Delta
+                }
+                None => Id,
+            },
+            Node::Bound(_) => Id,
+            Node::Ind(_) => unreachable!("indirections are followed"),
+        };
+        if kind == Id {
+            self.normal[id] = true;
+        }
+        kind
+    }
+
+    fn expand(&mut self, variable: Variable<'a>) -> Option<NodeId> {
+        if let Some(id) = self.expanded.get(&variable) {
+            return Some(*id);
+        }
+        let definition = self.definitions.get(&variable)?.clone();
+        let id = self.insert(&definition, &mut Vec::new());
+        self.expanded.insert(variable, id);
+        Some(id)
+    }
+
+    /// Copies the body of an abstraction, replacing its variable with the argument.
+    /// Parts of the body that don't refer to the variable are shared instead of copied.
+    fn instantiate(&mut self, body: NodeId, binder: NodeId, argument: NodeId) -> NodeId {
+        let mut copies = Copies {
+            binder,
+            argument,
+            nodes: HashMap::new(),
+        };
+        self.copy(body, &mut copies).unwrap_or(body)
+    }
+
+    /// Returns `None` if the subgraph doesn't need to be copied.
+    fn copy(&mut self, id: NodeId, copies: &mut Copies) -> Option<NodeId> {
+        let id = self.follow(id);
+        if let Some(copy) = copies.nodes.get(&id) {
+            return Some(*copy);
+        }
+        let copy = match self.nodes[id] {
+            Node::Free(_) => None,
+            Node::Bound(binder) if binder == copies.binder => Some(copies.argument),
+            Node::Bound(binder) => {
+                let binder = *copies.nodes.get(&binder)?;
+                Some(self.push(Node::Bound(binder)))
+            }
+            Node::Abs(variable, body) => {
+                let placeholder = self.push(Node::Abs(variable, body));
+                copies.nodes.insert(id, placeholder);
+                let body = self.copy(body, copies);
+                copies.nodes.remove(&id);
+                body.map(|body| {
+                    self.nodes[placeholder] = Node::Abs(variable, body);
+                    placeholder
+                })
+            }
+            Node::App(left, right) => match (self.copy(left, copies), self.copy(right, copies)) {
+                (None, None) => None,
+                (left_copy, right_copy) => {
+                    let left = left_copy.unwrap_or(left);
+                    let right = right_copy.unwrap_or(right);
+                    Some(self.push(Node::App(left, right)))
+                }
+            },
+            Node::Ind(_) => unreachable!("indirections are followed"),
+        };
+        if let Some(copy) = copy {
+            copies.nodes.insert(id, copy);
+        }
+        copy
+    }
+
+    fn follow(&self, mut id: NodeId) -> NodeId {
+        while let Node::Ind(target) = self.nodes[id] {
+            id = target;
+        }
+        id
+    }
+
+    fn push(&mut self, node: Node<'a>) -> NodeId {
+        self.nodes.push(node);
+        self.normal.push(false);
+        self.nodes.len() - 1
+    }
+
+    fn insert(&mut self, term: &Term<'a>, binders: &mut Vec<(Variable<'a>, NodeId)>) -> NodeId {
+        match term {
+            Term::Var(variable) => {
+                let binder = binders.iter().rev().find(|(bound, _)| {
+                    bound == variable && variable.disambiguator != Disambiguator::Symbol
+                });
+                match binder {
+                    Some((_, binder)) => self.push(Node::Bound(*binder)),
+                    None => self.push(Node::Free(*variable)),
+                }
+            }
+            Abs! { variable, term } => {
+                let id = self.push(Node::Abs(*variable, 0));
+                binders.push((*variable, id));
+                let body = self.insert(term, binders);
+                binders.pop();
+                self.nodes[id] = Node::Abs(*variable, body);
+                id
+            }
+            App! { left, right } => {
+                let left = self.insert(left, binders);
+                let right = self.insert(right, binders);
+                self.push(Node::App(left, right))
+            }
+        }
+    }
+
+    fn to_de_bruijn(&self, id: NodeId, binders: &mut Vec<NodeId>) -> DeBruijnTerm<'a> {
+        let id = self.follow(id);
+        match self.nodes[id] {
+            Node::Free(variable) => DeBruijnTerm::Free(variable),
+            Node::Bound(binder) => {
+                let index = binders.iter().rev().position(|b| *b == binder);
+                DeBruijnTerm::Bound(index.expect("bound variables are below their binder"))
+            }
+            Node::Abs(variable, body) => {
+                binders.push(id);
+                let body = self.to_de_bruijn(body, binders);
+                binders.pop();
+                DeBruijnTerm::Abs(variable, Box::new(body))
+            }
+            Node::App(left, right) => DeBruijnTerm::App(
+                Box::new(self.to_de_bruijn(left, binders)),
+                Box::new(self.to_de_bruijn(right, binders)),
+            ),
+            Node::Ind(_) => unreachable!("indirections are followed"),
+        }
+    }
+
+    /// Moves all nodes that are still reachable into a new arena.
+    fn collect_garbage(&mut self) {
+        let mut collected = Graph {
+            nodes: Vec::new(),
+            normal: Vec::new(),
+            root: 0,
+            definitions: Definitions::default(),
+            expanded: HashMap::new(),
+            live: 0,
+        };
+        let mut moved = HashMap::new();
+        collected.root = self.move_node(self.root, &mut collected, &mut moved);
+        for (variable, id) in std::mem::take(&mut self.expanded) {
+            let id = self.move_node(id, &mut collected, &mut moved);
+            collected.expanded.insert(variable, id);
+        }
+        self.nodes = collected.nodes;
+        self.normal = collected.normal;
+        self.root = collected.root;
+        self.expanded = collected.expanded;
+        self.live = self.nodes.len();
+    }
+
+    fn move_node(
+        &self,
+        id: NodeId,
+        collected: &mut Graph<'a>,
+        moved: &mut HashMap<NodeId, NodeId>,
+    ) -> NodeId {
+        let id = self.follow(id);
+        if let Some(new_id) = moved.get(&id) {
+            return *new_id;
+        }
+        let new_id = collected.push(self.nodes[id]);
+        collected.normal[new_id] = self.normal[id];
+        moved.insert(id, new_id);
+        collected.nodes[new_id] = match self.nodes[id] {
+            node @ Node::Free(_) => node,
+            Node::Bound(binder) => Node::Bound(moved[&binder]),
+            Node::Abs(variable, body) => {
+                Node::Abs(variable, self.move_node(body, collected, moved))
+            }
+            Node::App(left, right) => Node::App(
+                self.move_node(left, collected, moved),
+                self.move_node(right, collected, moved),
+            ),
+            Node::Ind(_) => unreachable!("indirections are followed"),
+        };
+        new_id
+    }
+}
+
+/// Evaluates a program's term in normal order using a [`Graph`] that shares arguments
+/// between all occurrences of their variable, so that each argument is reduced only once.
+pub fn evaluate_graph<'a>(program: impl Into<Program<'a>>) -> impl Iterator<Item = Step<'a>> {
+    let mut graph = Graph::new(program);
+    iter::from_fn(move || match graph.reduce() {
+        Id => None,
+        kind => Some(Step::new(kind, graph.to_term())),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_back_original_term() {
+        let term = λ![λx.λy.x (λx.x y) Z];
+        assert_eq!(term, Graph::new(term.clone()).to_term());
+    }
+
+    #[test]
+    fn reduces_to_normal_form() {
+        let term = λ![(λf.λx.f (f x)) (λf.λx.f (f x)) (λy.λx.y) x];
+        let expected = evaluate(term.clone()).last().unwrap().term;
+        let actual = evaluate_graph(term).last().unwrap().term;
+        assert!(alpha_eq(&expected, &actual), "{expected} = {actual}");
+    }
+
+    #[test]
+    fn reduces_shared_argument_once() {
+        let term = λ![(λx.x x x) ((λy.y) Z)];
+        let kinds: Vec<_> = evaluate_graph(term).map(|step| step.kind).collect();
+        assert_eq!(vec![Beta, Beta], kinds);
+    }
+
+    #[test]
+    fn reduced_argument_appears_in_every_occurrence() {
+        let term = λ![(λx.x x) ((λy.y) Z)];
+        let terms: Vec<_> = evaluate_graph(term).map(|step| step.term).collect();
+        assert_eq!(vec![λ![(λy.y) Z ((λy.y) Z)], λ![Z Z]], terms);
+    }
+
+    #[test]
+    fn does_not_capture_variables_of_argument() {
+        let steps: Vec<_> = evaluate_graph(λ![(λy.λx.y) x]).collect();
+        assert_eq!(vec![Step::new(Beta, abs(("x", 1), var("x")))], steps);
+    }
+
+    #[test]
+    fn copies_abstraction_that_is_applied_more_than_once() {
+        let term = λ![(λf.f A (f B)) (λx.x)];
+        let normal_form = evaluate_graph(term).last().unwrap().term;
+        assert_eq!(λ![A B], normal_form);
+    }
+
+    #[test]
+    fn expands_definitions() {
+        let definitions = [(Variable::new("I"), λ![λx.x])].into_iter().collect();
+        let steps: Vec<_> = evaluate_graph(Program::new(definitions, λ![I I Y])).collect();
+        let kinds: Vec<_> = steps.iter().map(|step| step.kind.clone()).collect();
+        assert_eq!(vec![Delta, Beta, Delta, Beta], kinds);
+        assert_eq!(λ![Y], steps.last().unwrap().term);
+    }
+
+    #[test]
+    fn collects_garbage_without_changing_term() {
+        let term = λ![(λx.x x) ((λy.y) (λz.z))];
+        let mut graph = Graph::new(term);
+        graph.reduce();
+        let before = graph.to_term();
+        graph.collect_garbage();
+        assert_eq!(before, graph.to_term());
+        assert_eq!(Beta, graph.reduce());
+    }
+}
@@ -0,0 +1,48 @@
+//! Head reduction to head normal form `λx₁…xₙ.y M₁…Mₘ`: only the head redex is contracted,
+//! which unlike call-by-name includes redexes below abstractions, but never arguments.
+use super::*;
+use StepKind::*;
+use Term::*;
+
+pub(crate) fn reduce<'a>(
+    term: impl Into<Term<'a>>,
+    environment: &mut Environment<'a, impl EffectHandler<'a>>,
+) -> Step<'a> {
+    match term.into() {
+        App! { left: Abs(abs), right, span } => rename_and_substitute(*abs, right, span),
+        App! { left, right, span } => match reduce(left, environment).id_or_err() {
+            Ok(left) if is_symbol(&left) => perform_with(left, right, span, environment, reduce),
+            Ok(left) => Step::new(Id, app(left, right).with_span(span)),
+            Err(left) => left.map(&[Direction::Left], |left| app(left, right).with_span(span)),
+        },
+        Abs! { variable, term, span } => reduce(term, environment)
+            .map(&[Direction::Body], |term| {
+                abs(variable, term).with_span(span)
+            }),
+        Var(variable) => expand(variable, &environment.definitions),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reduces_below_abstraction() {
+        let expression = λ![ λx.(λy.y) x ];
+        let expected = λ![λx.x];
+        assert_eq!(
+            expected,
+            reduce(expression, &mut Environment::<NoSideEffects>::default()).term
+        );
+    }
+
+    #[test]
+    fn does_not_reduce_arguments_of_variable_in_head_position() {
+        let expression = λ![ λx.x ((λy.y) Y) ];
+        assert_eq!(
+            Id,
+            reduce(expression, &mut Environment::<NoSideEffects>::default()).kind
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use crate::evaluation::{
+    abs, app, free_variables, Abstraction, Application, Decode, Disambiguator, Encode, Term,
+    Variable,
+};
+use std::fmt;
+use std::str::FromStr;
+
+/// How a list is represented as a λ-[`Term`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ListEncoding {
+    /// A list is its right fold, e.g. `[a, b]` is `λc n.c a (c b n)`.
+    /// This is how the lists of the [`PRELUDE`](crate::PRELUDE) work.
+    #[default]
+    Church,
+    /// A list is a case distinction between cons and nil, e.g. `[a, b]` is
+    /// `λc n.c a (λc n.c b (λc n.n))`.
+    Scott,
+}
+
+impl ListEncoding {
+    pub const ALL: [ListEncoding; 2] = [ListEncoding::Church, ListEncoding::Scott];
+}
+
+impl fmt::Display for ListEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListEncoding::Church => f.write_str("church"),
+            ListEncoding::Scott => f.write_str("scott"),
+        }
+    }
+}
+
+impl FromStr for ListEncoding {
+    type Err = UnknownListEncodingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|encoding| encoding.to_string() == s)
+            .ok_or_else(|| UnknownListEncodingError(s.to_owned()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownListEncodingError(pub String);
+
+impl fmt::Display for UnknownListEncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown list encoding `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownListEncodingError {}
+
+/// Encodes the items as a list. The variables of the encoding are chosen
+/// such that they don't capture free variables of the items.
+pub fn encode_list<'a>(items: Vec<Term<'a>>, encoding: ListEncoding) -> Term<'a> {
+    let free: Vec<_> = items.iter().flat_map(free_variables).collect();
+    let fresh = |name| {
+        (0..)
+            .map(|index| match index {
+                0 => Variable::new(name),
+                _ => Variable::new_with(name, Disambiguator::Numeric(index)),
+            })
+            .find(|variable| !free.contains(variable))
+            .unwrap_or_else(|| unreachable!())
+    };
+    let (cons, nil) = (fresh("c"), fresh("n"));
+    let items = items.into_iter().rev();
+    match encoding {
+        ListEncoding::Church => abs(
+            cons,
+            abs(
+                nil,
+                items.fold(Term::Var(nil), |list, item| app(app(cons, item), list)),
+            ),
+        ),
+        ListEncoding::Scott => items.fold(abs(cons, abs(nil, nil)), |list, item| {
+            abs(cons, abs(nil, app(app(cons, item), list)))
+        }),
+    }
+}
+
+/// Finds the items of a list in either encoding.
+///
+/// Items can't use the variables that the list binds, so `λc n.c n n` isn't the list `[n]`.
+pub fn decode_list<'t, 'a>(term: &'t Term<'a>) -> Option<Vec<&'t Term<'a>>> {
+    let Abs! { variable: cons, term: Abs! { variable: nil, term: body } } = term else {
+        return None;
+    };
+    let binders = vec![*cons, *nil];
+    let (mut body, mut items) = (body, Vec::new());
+    loop {
+        match body {
+            Term::Var(v) if v == nil => return Some(items),
+            App! { left: App! { left: Term::Var(c), right: item }, right: rest } if c == cons => {
+                items.push(unbound_item(item, &binders)?);
+                match rest {
+                    // Only the tail of a Scott list is a list itself
+                    Term::Abs(_) if items.len() == 1 => {
+                        return decode_scott_tail(rest, items, binders)
+                    }
+                    _ => body = rest,
+                }
+            }
+            _ => return None,
+        }
+    }
+}
+
+fn decode_scott_tail<'t, 'a>(
+    mut term: &'t Term<'a>,
+    mut items: Vec<&'t Term<'a>>,
+    mut binders: Vec<Variable<'a>>,
+) -> Option<Vec<&'t Term<'a>>> {
+    loop {
+        let Abs! { variable: cons, term: Abs! { variable: nil, term: body } } = term else {
+            return None;
+        };
+        binders.extend([*cons, *nil]);
+        match body {
+            Term::Var(v) if v == nil => return Some(items),
+            App! { left: App! { left: Term::Var(c), right: item }, right: rest } if c == cons => {
+                items.push(unbound_item(item, &binders)?);
+                term = rest;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// The item if none of the variables that the list binds are free in it.
+fn unbound_item<'t, 'a>(item: &'t Term<'a>, binders: &[Variable<'a>]) -> Option<&'t Term<'a>> {
+    let free = free_variables(item);
+    match binders.iter().any(|binder| free.contains(binder)) {
+        true => None,
+        false => Some(item),
+    }
+}
+
+/// Encodes a vector as a Church list, see [`encode_list`] for other encodings.
+impl<'a, T: Encode<'a>> Encode<'a> for Vec<T> {
+    fn encode(&self) -> Term<'a> {
+        encode_list(self.iter().map(T::encode).collect(), ListEncoding::Church)
+    }
+}
+
+/// Decodes a list in either [`ListEncoding`].
+impl<'a, T: Decode<'a>> Decode<'a> for Vec<T> {
+    fn decode(term: &Term<'a>) -> Option<Self> {
+        decode_list(term)?.into_iter().map(T::decode).collect()
+    }
+}
+
+/// Encodes a string as the Church list of the Church numerals of its characters.
+///
+/// A character nests as many applications as its code point, so strings with characters
+/// above [`Numeral::MAX`](crate::syntax::Numeral::MAX) build terms too deep to evaluate,
+/// which is why string literals can't have them.
+impl Encode<'static> for str {
+    fn encode(&self) -> Term<'static> {
+        let characters = self.chars().map(|c| u64::from(c).encode()).collect();
+        encode_list(characters, ListEncoding::Church)
+    }
+}
+
+impl Encode<'static> for String {
+    fn encode(&self) -> Term<'static> {
+        self.as_str().encode()
+    }
+}
+
+impl Decode<'_> for String {
+    fn decode(term: &Term<'_>) -> Option<Self> {
+        Vec::<u64>::decode(term)?
+            .into_iter()
+            .map(|c| char::from_u32(c.try_into().ok()?))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation::{alpha_eq, var, var_with};
+
+    #[test]
+    fn encodes_lists() {
+        let items = || vec![var("a"), var("b")];
+        assert!(alpha_eq(
+            &λ!(λc n.c a (c b n)),
+            &encode_list(items(), ListEncoding::Church)
+        ));
+        assert!(alpha_eq(
+            &λ!(λc n.c a (λc n.c b (λc n.n))),
+            &encode_list(items(), ListEncoding::Scott)
+        ));
+    }
+
+    #[test]
+    fn does_not_capture_free_variables_of_items() {
+        let list = encode_list(vec![var("c"), var("n")], ListEncoding::Church);
+        let (cons, nil) = (var_with("c", 1), var_with("n", 1));
+        let expected = abs(
+            Variable::new_with("c", 1),
+            abs(
+                Variable::new_with("n", 1),
+                app(app(cons.clone(), var("c")), app(app(cons, var("n")), nil)),
+            ),
+        );
+        assert_eq!(expected, list);
+    }
+
+    #[test]
+    fn decodes_both_encodings() {
+        for encoding in ListEncoding::ALL {
+            for items in [vec![], vec![1], vec![1, 2, 3]] {
+                let list = encode_list(items.iter().map(u64::encode).collect(), encoding);
+                assert_eq!(Some(items), list.decode(), "{list}");
+            }
+        }
+    }
+
+    #[test]
+    fn does_not_decode_items_that_use_the_variables_of_the_list() {
+        assert_eq!(None, decode_list(&λ!(λc n.c n n)));
+        assert_eq!(None, decode_list(&λ!(λc n.c a (c c n))));
+        assert_eq!(None, decode_list(&λ!(λc n.c a (λd m.d n (λd m.m)))));
+        assert_eq!(Some(vec![&λ!(λn.n)]), decode_list(&λ!(λc n.c (λn.n) n)));
+    }
+
+    #[test]
+    fn decodes_only_the_tail_of_one_item_as_a_scott_list() {
+        let church = λ!(λc n.c a n);
+        let scott = λ!(λc n.c a (λc n.n));
+        assert_eq!(Some(vec![&var("a")]), decode_list(&church));
+        assert_eq!(Some(vec![&var("a")]), decode_list(&scott));
+        // A Church list's items come before its tail, which is never an abstraction
+        assert_eq!(None, decode_list(&λ!(λc n.c a (c b (λc n.n)))));
+        assert_eq!(None, decode_list(&λ!(λc n.c a (λx.x))));
+        assert_eq!(None, decode_list(&λ!(λc n.c a (λc n.c b n))));
+    }
+
+    #[test]
+    fn strings_round_trip() {
+        let text = "λ-calculus";
+        assert_eq!(Some(text.to_owned()), text.encode().decode());
+    }
+}
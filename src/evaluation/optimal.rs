@@ -0,0 +1,588 @@
+//! An experimental optimal reducer based on Lamping's algorithm
+//! (in the formulation of Gonthier, Abadi and Lévy).
+//!
+//! A term is translated into an interaction net (a *sharing graph*) with λ- and @-nodes,
+//! fans that share subgraphs and the control nodes *croissant* and *bracket*
+//! that keep track of which fans belong together. Every node has a level and
+//! two nodes interact when they face each other with their principal ports:
+//! ```text
+//! λᵢ @ᵢ                β-reduction
+//! ▽ᵢ ▽ᵢ, ⌓ᵢ ⌓ᵢ, ...    annihilation of nodes with the same kind and level
+//! ▽ᵢ X                 duplication of X (or commutation in general)
+//! ε X                  erasure
+//! ```
+//! Interactions are performed lazily along the path that reads the term back,
+//! i.e. in normal order, so that no argument is ever reduced more than once.
+//!
+//! See: J. Lamping, "An algorithm for optimal lambda calculus reduction" (POPL '90) and
+//! G. Gonthier, M. Abadi, J.-J. Lévy, "The geometry of optimal lambda reduction" (POPL '92).
+use super::*;
+use std::collections::HashMap;
+
+/// The statistics of an optimal reduction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InteractionStats {
+    /// Interactions of a λ- with an @-node.
+    pub beta: usize,
+    /// Interactions of two nodes of the same kind and level that cancel each other out.
+    pub annihilation: usize,
+    /// Interactions where two nodes pass through each other (e.g. a fan duplicating a λ-node).
+    pub commutation: usize,
+    /// Interactions with an eraser.
+    pub erasure: usize,
+}
+
+impl InteractionStats {
+    pub fn total(&self) -> usize {
+        self.beta + self.annihilation + self.commutation + self.erasure
+    }
+}
+
+/// The normal form of a term reduced by [`reduce_optimally`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptimalReduction<'a> {
+    pub normal_form: Term<'a>,
+    pub stats: InteractionStats,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptimalReductionError {
+    /// The term didn't reach a normal form within the maximum number of interactions.
+    InteractionLimitReached(InteractionStats),
+    /// The net couldn't be read back as a term. This is a bug in the reducer.
+    ReadBack,
+}
+
+/// Reduces a term to normal form using optimal reduction,
+/// giving up after `max_interactions` interactions.
+pub fn reduce_optimally<'a>(
+    term: &Term<'a>,
+    max_interactions: usize,
+) -> Result<OptimalReduction<'a>, OptimalReductionError> {
+    let mut net = Net::new(term, max_interactions);
+    match net.read_back() {
+        Ok(term) => Ok(OptimalReduction {
+            normal_form: term.into(),
+            stats: net.stats,
+        }),
+        Err(Stop::InteractionLimitReached) => {
+            Err(OptimalReductionError::InteractionLimitReached(net.stats))
+        }
+        Err(Stop::Interacted | Stop::Stuck) => Err(OptimalReductionError::ReadBack),
+    }
+}
+
+type NodeId = usize;
+
+/// Port `0` is the principal port of a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Port {
+    node: NodeId,
+    slot: usize,
+}
+
+const fn port(node: NodeId, slot: usize) -> Port {
+    Port { node, slot }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind<'a> {
+    /// Connects to the root of the term.
+    Root,
+    /// Ports: principal, body, variable.
+    Lam(Variable<'a>),
+    /// Ports: function, result, argument.
+    App,
+    /// Ports: principal, left, right.
+    Fan,
+    /// Decrements the level of nodes that pass from its principal to its auxiliary port.
+    Croissant,
+    /// Increments the level of nodes that pass from its principal to its auxiliary port.
+    Bracket,
+    Eraser,
+    /// A free variable.
+    Free(Variable<'a>),
+}
+
+impl<'a> Kind<'a> {
+    fn arity(&self) -> usize {
+        match self {
+            Kind::Lam(_) | Kind::App | Kind::Fan => 2,
+            Kind::Croissant | Kind::Bracket => 1,
+            Kind::Root | Kind::Eraser | Kind::Free(_) => 0,
+        }
+    }
+
+    fn is_control(&self) -> bool {
+        matches!(self, Kind::Croissant | Kind::Bracket)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node<'a> {
+    kind: Kind<'a>,
+    level: usize,
+    ports: [Port; 3],
+}
+
+struct Net<'a> {
+    nodes: Vec<Option<Node<'a>>>,
+    free: Vec<NodeId>,
+    root: NodeId,
+    stats: InteractionStats,
+    max_interactions: usize,
+}
+
+/// Why reading back (a part of) the net stopped.
+enum Stop {
+    /// The node that was read last interacted, so reading has to go on from the node before.
+    Interacted,
+    /// Two nodes have to interact before reading on, but the maximum number of
+    /// interactions was reached.
+    InteractionLimitReached,
+    Stuck,
+}
+
+type FreeVariables<'a> = HashMap<Variable<'a>, Port>;
+
+impl<'a> Net<'a> {
+    fn new(term: &Term<'a>, max_interactions: usize) -> Self {
+        let mut net = Net {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: 0,
+            stats: InteractionStats::default(),
+            max_interactions,
+        };
+        net.root = net.add(Kind::Root, 0);
+        let (root, free) = net.translate(term, 0);
+        net.link(port(net.root, 0), root);
+        for (variable, port) in free {
+            let free = net.add(Kind::Free(variable), 0);
+            net.link(self::port(free, 0), port);
+        }
+        net
+    }
+
+    /// Translates a term at the given level, returning the port for its root and
+    /// the ports that lead to the binders of its free variables.
+    fn translate(&mut self, term: &Term<'a>, level: usize) -> (Port, FreeVariables<'a>) {
+        match term {
+            Term::Var(variable) => {
+                let croissant = self.add(Kind::Croissant, level);
+                (
+                    port(croissant, 1),
+                    HashMap::from([(*variable, port(croissant, 0))]),
+                )
+            }
+            Abs! { variable, term } => {
+                let (body, mut free) = self.translate(term, level);
+                let lam = self.add(Kind::Lam(*variable), level);
+                self.link(port(lam, 1), body);
+                let occurrences = match free.remove(variable) {
+                    Some(occurrences) => occurrences,
+                    None => port(self.add(Kind::Eraser, level), 0),
+                };
+                self.link(port(lam, 2), occurrences);
+                (port(lam, 0), free)
+            }
+            App! { left, right } => {
+                let (function, mut free) = self.translate(left, level);
+                let (argument, argument_free) = self.translate(right, level + 1);
+                let app = self.add(Kind::App, level);
+                self.link(port(app, 0), function);
+                self.link(port(app, 2), argument);
+                for (variable, occurrences) in argument_free {
+                    let bracket = self.add(Kind::Bracket, level);
+                    self.link(port(bracket, 1), occurrences);
+                    let occurrences = match free.remove(&variable) {
+                        Some(other) => {
+                            let fan = self.add(Kind::Fan, level);
+                            self.link(port(fan, 1), other);
+                            self.link(port(fan, 2), port(bracket, 0));
+                            port(fan, 0)
+                        }
+                        None => port(bracket, 0),
+                    };
+                    free.insert(variable, occurrences);
+                }
+                (port(app, 1), free)
+            }
+        }
+    }
+
+    fn add(&mut self, kind: Kind<'a>, level: usize) -> NodeId {
+        let node = Node {
+            kind,
+            level,
+            ports: [port(usize::MAX, 0); 3],
+        };
+        match self.free.pop() {
+            Some(id) => {
+                self.nodes[id] = Some(node);
+                id
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    fn remove(&mut self, id: NodeId) {
+        self.nodes[id] = None;
+        self.free.push(id);
+    }
+
+    fn node(&self, id: NodeId) -> &Node<'a> {
+        self.nodes[id].as_ref().expect("node is alive")
+    }
+
+    fn neighbor(&self, port: Port) -> Port {
+        self.node(port.node).ports[port.slot]
+    }
+
+    fn link(&mut self, left: Port, right: Port) {
+        self.nodes[left.node].as_mut().unwrap().ports[left.slot] = right;
+        self.nodes[right.node].as_mut().unwrap().ports[right.slot] = left;
+    }
+
+    fn interacts(&self, left: NodeId, right: NodeId) -> bool {
+        match (self.node(left).kind, self.node(right).kind) {
+            (Kind::Root, _) | (_, Kind::Root) => false,
+            // Free variables have no level and are only ever erased,
+            // the read back finds them through any fans and control nodes in between.
+            (Kind::Free(_), other) | (other, Kind::Free(_)) => other == Kind::Eraser,
+            _ => true,
+        }
+    }
+
+    fn interact(&mut self, left: NodeId, right: NodeId) {
+        let (a, b) = (self.node(left).clone(), self.node(right).clone());
+        match (a.kind, b.kind) {
+            (Kind::Lam(_), Kind::App) | (Kind::App, Kind::Lam(_)) if a.level == b.level => {
+                self.stats.beta += 1;
+                self.annihilate(left, right, &a, &b)
+            }
+            (Kind::Eraser, _) => {
+                self.stats.erasure += 1;
+                self.erase(left, right, &b)
+            }
+            (_, Kind::Eraser) => {
+                self.stats.erasure += 1;
+                self.erase(right, left, &a)
+            }
+            (left_kind, right_kind)
+                if a.level == b.level
+                    && (left_kind == right_kind
+                        || left_kind.is_control() && right_kind.is_control()) =>
+            {
+                self.stats.annihilation += 1;
+                self.annihilate(left, right, &a, &b)
+            }
+            (Kind::Lam(_), Kind::App) | (Kind::App, Kind::Lam(_)) => {
+                unreachable!("λ- and @-nodes only meet on the same level")
+            }
+            _ => {
+                self.stats.commutation += 1;
+                self.commute(left, right, &a, &b)
+            }
+        }
+    }
+
+    /// Connects the auxiliary ports of two nodes pairwise.
+    /// For a λ- and an @-node this connects the body with the result
+    /// and the variable with the argument.
+    fn annihilate(&mut self, left: NodeId, right: NodeId, a: &Node<'a>, b: &Node<'a>) {
+        self.remove(left);
+        self.remove(right);
+        for slot in 1..=a.kind.arity() {
+            self.link(a.ports[slot], b.ports[slot]);
+        }
+    }
+
+    fn erase(&mut self, eraser: NodeId, erased: NodeId, node: &Node<'a>) {
+        self.remove(eraser);
+        self.remove(erased);
+        for slot in 1..=node.kind.arity() {
+            let eraser = self.add(Kind::Eraser, 0);
+            self.link(port(eraser, 0), node.ports[slot]);
+        }
+    }
+
+    /// Lets two nodes pass through each other: every auxiliary port of one node gets
+    /// a copy of the other node. A node that passes a control node with a lower level
+    /// has its level adjusted.
+    fn commute(&mut self, left: NodeId, right: NodeId, a: &Node<'a>, b: &Node<'a>) {
+        self.remove(left);
+        self.remove(right);
+        let a_copies: Vec<_> = (1..=b.kind.arity())
+            .map(|_| self.add(a.kind, passed_level(a, b)))
+            .collect();
+        let b_copies: Vec<_> = (1..=a.kind.arity())
+            .map(|_| self.add(b.kind, passed_level(b, a)))
+            .collect();
+        for (k, b_copy) in b_copies.iter().enumerate() {
+            self.link(port(*b_copy, 0), a.ports[k + 1]);
+        }
+        for (l, a_copy) in a_copies.iter().enumerate() {
+            self.link(port(*a_copy, 0), b.ports[l + 1]);
+        }
+        for (k, b_copy) in b_copies.iter().enumerate() {
+            for (l, a_copy) in a_copies.iter().enumerate() {
+                self.link(port(*b_copy, l + 1), port(*a_copy, k + 1));
+            }
+        }
+    }
+
+    /// Reads back the term the net represents, performing the interactions on the way.
+    fn read_back(&mut self) -> Result<DeBruijnTerm<'a>, Stop> {
+        self.read(port(self.root, 0), Context::default(), &mut Vec::new())
+    }
+
+    /// Reads the term at the other end of the wire connected to `from`.
+    ///
+    /// If the node of `from` has to interact with the one at the other end first, it does so
+    /// and the caller reads again from where it got to that node, which is gone now.
+    fn read(
+        &mut self,
+        from: Port,
+        context: Context,
+        binders: &mut Vec<(NodeId, Context)>,
+    ) -> Result<DeBruijnTerm<'a>, Stop> {
+        loop {
+            let to = self.neighbor(from);
+            if from.slot == 0 && to.slot == 0 && self.interacts(from.node, to.node) {
+                if self.stats.total() >= self.max_interactions {
+                    return Err(Stop::InteractionLimitReached);
+                }
+                self.interact(from.node, to.node);
+                return Err(Stop::Interacted);
+            }
+            match self.read_node(to, context.clone(), binders) {
+                Err(Stop::Interacted) => continue,
+                result => return result,
+            }
+        }
+    }
+
+    /// Reads the term that starts where reading got to the node of `to` through that port.
+    fn read_node(
+        &mut self,
+        to: Port,
+        mut context: Context,
+        binders: &mut Vec<(NodeId, Context)>,
+    ) -> Result<DeBruijnTerm<'a>, Stop> {
+        let Node { kind, level, .. } = *self.node(to.node);
+        match (kind, to.slot) {
+            (Kind::Lam(variable), 0) => {
+                binders.push((to.node, context.clone()));
+                let body = self.read(port(to.node, 1), context, binders);
+                binders.pop();
+                Ok(DeBruijnTerm::Abs(variable, Box::new(body?)))
+            }
+            (Kind::Lam(_), 2) => {
+                let below_level = |(id, binder): &(NodeId, Context)| {
+                    *id == to.node && binder.below(level) == context.below(level)
+                };
+                let index = binders
+                    .iter()
+                    .rev()
+                    .position(below_level)
+                    .ok_or(Stop::Stuck)?;
+                Ok(DeBruijnTerm::Bound(index))
+            }
+            (Kind::App, 1) => {
+                let function = self.read(port(to.node, 0), context.clone(), binders)?;
+                let argument = self.read(port(to.node, 2), context, binders)?;
+                Ok(DeBruijnTerm::App(Box::new(function), Box::new(argument)))
+            }
+            (Kind::Fan, 0) => {
+                let slot = context.pop_choice(level).ok_or(Stop::Stuck)?;
+                self.read(port(to.node, slot), context, binders)
+            }
+            (Kind::Fan, slot) => {
+                context.level(level).push(Choice::Slot(slot));
+                self.read(port(to.node, 0), context, binders)
+            }
+            (Kind::Croissant, 0) => {
+                context.remove(level);
+                self.read(port(to.node, 1), context, binders)
+            }
+            (Kind::Croissant, _) => {
+                context.insert(level, Vec::new());
+                self.read(port(to.node, 0), context, binders)
+            }
+            (Kind::Bracket, 0) => {
+                let nested = context.pop_nested(level).ok_or(Stop::Stuck)?;
+                context.insert(level + 1, nested);
+                self.read(port(to.node, 1), context, binders)
+            }
+            (Kind::Bracket, _) => {
+                let nested = context.remove(level + 1);
+                context.level(level).push(Choice::Nested(nested));
+                self.read(port(to.node, 0), context, binders)
+            }
+            (Kind::Free(variable), 0) => Ok(DeBruijnTerm::Free(variable)),
+            _ => Err(Stop::Stuck),
+        }
+    }
+}
+
+/// The level a node ends up on after passing through `other`.
+fn passed_level(node: &Node, other: &Node) -> usize {
+    let passes = other.level < node.level || other.level == node.level && !node.kind.is_control();
+    match other.kind {
+        Kind::Croissant if passes => node.level - 1,
+        Kind::Bracket if passes => node.level + 1,
+        _ => node.level,
+    }
+}
+
+/// Which way a path went through the nodes it passed so far (Lamping's context semantics).
+/// Each level is a stack that records which auxiliary port of a fan the path entered through,
+/// so that it leaves through the same port once it passes a matching fan in the opposite direction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Context(Vec<Vec<Choice>>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Choice {
+    Slot(usize),
+    /// A level that was merged into the one below by a bracket.
+    Nested(Vec<Choice>),
+}
+
+impl Context {
+    fn level(&mut self, level: usize) -> &mut Vec<Choice> {
+        if self.0.len() <= level {
+            self.0.resize(level + 1, Vec::new());
+        }
+        &mut self.0[level]
+    }
+
+    fn insert(&mut self, level: usize, choices: Vec<Choice>) {
+        self.level(level);
+        self.0.insert(level, choices);
+    }
+
+    fn remove(&mut self, level: usize) -> Vec<Choice> {
+        self.level(level);
+        self.0.remove(level)
+    }
+
+    fn pop_choice(&mut self, level: usize) -> Option<usize> {
+        match self.level(level).pop()? {
+            Choice::Slot(slot) => Some(slot),
+            Choice::Nested(_) => None,
+        }
+    }
+
+    fn pop_nested(&mut self, level: usize) -> Option<Vec<Choice>> {
+        match self.level(level).pop()? {
+            Choice::Nested(nested) => Some(nested),
+            Choice::Slot(_) => None,
+        }
+    }
+
+    /// The levels below the given one with trailing empty levels removed.
+    fn below(&self, level: usize) -> &[Vec<Choice>] {
+        let below = &self.0[..level.min(self.0.len())];
+        let len = below.len() - below.iter().rev().take_while(|l| l.is_empty()).count();
+        &below[..len]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_INTERACTIONS: usize = 100_000;
+
+    #[test]
+    fn reads_back_terms_in_normal_form() {
+        let terms = [
+            λ![x],
+            λ![λx.x],
+            λ![λx.λy.x y z],
+            λ![λf.f (λx.x) f],
+            λ![X (λx.x x)],
+        ];
+        for term in terms {
+            let reduction = reduce_optimally(&term, MAX_INTERACTIONS).unwrap();
+            assert_eq!(term, reduction.normal_form);
+            assert_eq!(0, reduction.stats.total());
+        }
+    }
+
+    #[test]
+    fn reduces_to_same_normal_form_as_normal_order() {
+        let terms = [
+            λ![(λx.x) Y],
+            λ![(λx.λy.x) A B],
+            λ![(λx.x x) (λy.y)],
+            λ![(λx.Y) ((λx.x x) (λx.x x))],
+            λ![(λy.λx.y) x],
+            // S K K
+            λ![(λx y z.x z (y z)) (λx y.x) (λx y.x)],
+            // 2 + 3
+            λ![(λm n f x.m f (n f x)) (λf x.f (f x)) (λf x.f (f (f x)))],
+            // 2 * 3
+            λ![(λm n f.m (n f)) (λf x.f (f x)) (λf x.f (f (f x)))],
+            // 2 ^ 3
+            λ![(λf x.f (f (f x))) (λf x.f (f x))],
+            // 3 ^ 2
+            λ![(λf x.f (f x)) (λf x.f (f (f x)))],
+            λ![(λx.x x x) ((λy.y) Z)],
+            λ![(λf.f (f z)) (λx.λy.y x)],
+            λ![(λf.f (f I)) (λx.λw.x w w)],
+        ];
+        for term in terms {
+            let expected = evaluate(term.clone())
+                .last()
+                .map_or(term.clone(), |s| s.term);
+            let actual = reduce_optimally(&term, MAX_INTERACTIONS)
+                .unwrap()
+                .normal_form;
+            assert!(
+                alpha_eq(&expected, &actual),
+                "{term}: {expected} = {actual}"
+            );
+        }
+    }
+
+    #[test]
+    fn reduces_shared_redex_once() {
+        let term = λ![(λx.x x x) ((λy.y) Z)];
+        let reduction = reduce_optimally(&term, MAX_INTERACTIONS).unwrap();
+        assert_eq!(2, reduction.stats.beta);
+    }
+
+    #[test]
+    fn computes_factorial_with_fewer_beta_reductions_than_normal_order() {
+        let y = λ![λf.(λx.f (x x)) (λx.f (x x))];
+        let is_zero = λ![λn.n (λx t f.f) (λt f.t)];
+        let mul = λ![λm n f.m (n f)];
+        let pred = λ![λn f x.n (λg h.h (g f)) (λu.x) (λu.u)];
+        let body = app(
+            app(app(is_zero, var("n")), λ![λf x.f x]),
+            app(app(mul, var("n")), app(var("f"), app(pred, var("n")))),
+        );
+        let factorial = app(y, abs("f", abs("n", body)));
+        let term = app(factorial, λ![λf x.f (f (f x))]);
+
+        let reduction = reduce_optimally(&term, MAX_INTERACTIONS).unwrap();
+        assert_eq!(λ![λf.λx.f (f (f (f (f (f x)))))], reduction.normal_form);
+        let beta_steps = evaluate(term).filter(|s| s.kind == StepKind::Beta).count();
+        assert!(reduction.stats.beta < beta_steps);
+    }
+
+    #[test]
+    fn gives_up_after_maximum_number_of_interactions() {
+        let omega = λ![(λx.x x) (λx.x x)];
+        let error = reduce_optimally(&omega, 100).unwrap_err();
+        assert!(matches!(
+            error,
+            OptimalReductionError::InteractionLimitReached(stats) if stats.total() == 100
+        ));
+    }
+}
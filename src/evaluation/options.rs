@@ -0,0 +1,379 @@
+use super::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Limits that stop an evaluation early, together with the [`EvaluationStrategy`] to use.
+#[derive(Debug, Clone, Default)]
+pub struct EvaluationOptions {
+    pub strategy: EvaluationStrategy,
+    pub max_steps: Option<usize>,
+    pub max_term_size: Option<usize>,
+    pub deadline: Option<Instant>,
+    pub cancellation: Option<CancellationToken>,
+    pub detect_cycles: bool,
+}
+
+impl EvaluationOptions {
+    pub fn with_strategy(self, strategy: EvaluationStrategy) -> Self {
+        Self { strategy, ..self }
+    }
+
+    pub fn with_max_steps(self, max_steps: usize) -> Self {
+        Self {
+            max_steps: Some(max_steps),
+            ..self
+        }
+    }
+
+    pub fn with_max_term_size(self, max_term_size: usize) -> Self {
+        Self {
+            max_term_size: Some(max_term_size),
+            ..self
+        }
+    }
+
+    pub fn with_deadline(self, deadline: Instant) -> Self {
+        Self {
+            deadline: Some(deadline),
+            ..self
+        }
+    }
+
+    /// Sets the deadline relative to now.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    pub fn with_cancellation(self, cancellation: CancellationToken) -> Self {
+        Self {
+            cancellation: Some(cancellation),
+            ..self
+        }
+    }
+
+    /// Stops the evaluation once a term repeats (up to α-equivalence), see [`Outcome::Diverges`].
+    pub fn with_cycle_detection(self) -> Self {
+        Self {
+            detect_cycles: true,
+            ..self
+        }
+    }
+
+    /// Evaluates a program's term within the limits of these options.
+    pub fn evaluate<'a>(
+        self,
+        program: impl Into<Program<'a>>,
+    ) -> Evaluation<'a, impl Iterator<Item = Step<'a>>> {
+        self.evaluate_with_side_effects(program, NoSideEffects)
+    }
+
+    /// Like [`EvaluationOptions::evaluate`], but consults `effects` like [`evaluate_with_side_effects`].
+    pub fn evaluate_with_side_effects<'a>(
+        self,
+        program: impl Into<Program<'a>>,
+        effects: impl EffectHandler<'a>,
+    ) -> Evaluation<'a, impl Iterator<Item = Step<'a>>> {
+        let program = program.into();
+        let cycles = self.detect_cycles.then(|| {
+            let mut cycles = CycleDetector::default();
+            cycles.visit(0, &program.term);
+            cycles
+        });
+        let steps = self.strategy.evaluate_with_side_effects(program, effects);
+        Evaluation::new(steps, self, cycles)
+    }
+}
+
+/// A flag that can be shared with another thread (or a signal handler) to cancel an evaluation.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl From<Arc<AtomicBool>> for CancellationToken {
+    fn from(flag: Arc<AtomicBool>) -> Self {
+        CancellationToken(flag)
+    }
+}
+
+/// Why an evaluation stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Outcome {
+    /// The term can't be reduced any further by the strategy.
+    NormalForm,
+    /// The maximum number of steps was taken. The next step isn't computed, so that its
+    /// side effects don't happen, which means the term might already be in normal form.
+    StepLimitReached,
+    /// The next step would have produced a term larger than the maximum term size.
+    SizeLimitExceeded,
+    /// The deadline passed before a normal form was found.
+    DeadlineExceeded,
+    /// The evaluation was cancelled through its [`CancellationToken`].
+    Cancelled,
+    /// The term after `cycle_start + cycle_length` steps is α-equivalent to the term after
+    /// `cycle_start` steps without a side effect in between, so the evaluation would loop forever.
+    /// Only detected when enabled with [`EvaluationOptions::with_cycle_detection`], and possibly
+    /// a while after the term first repeats, so `cycle_start` may be later than where the cycle
+    /// begins.
+    Diverges {
+        cycle_start: usize,
+        cycle_length: usize,
+    },
+}
+
+/// An iterator over the steps of an evaluation that stops once a limit is hit.
+/// The reason for stopping is available through [`Evaluation::outcome`] afterwards.
+#[derive(Debug)]
+pub struct Evaluation<'a, I> {
+    steps: I,
+    options: EvaluationOptions,
+    count: usize,
+    outcome: Option<Outcome>,
+    cycles: Option<CycleDetector<'a>>,
+}
+
+impl<'a, I> Evaluation<'a, I> {
+    fn new(steps: I, options: EvaluationOptions, cycles: Option<CycleDetector<'a>>) -> Self {
+        Evaluation {
+            steps,
+            options,
+            count: 0,
+            outcome: None,
+            cycles,
+        }
+    }
+
+    /// Returns `None` while the evaluation hasn't stopped yet.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    fn interruption(&self) -> Option<Outcome> {
+        let options = &self.options;
+        if options
+            .cancellation
+            .as_ref()
+            .is_some_and(|c| c.is_cancelled())
+        {
+            Some(Outcome::Cancelled)
+        } else if options.deadline.is_some_and(|d| Instant::now() >= d) {
+            Some(Outcome::DeadlineExceeded)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, I: Iterator<Item = Step<'a>>> Iterator for Evaluation<'a, I> {
+    type Item = Step<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.outcome.is_some() {
+            return None;
+        }
+        if let Some(outcome) = self.interruption() {
+            self.outcome = Some(outcome);
+            return None;
+        }
+        if self.options.max_steps.is_some_and(|max| self.count >= max) {
+            self.outcome = Some(Outcome::StepLimitReached);
+            return None;
+        }
+        let step = self.steps.next();
+        self.outcome = match &step {
+            None => Some(Outcome::NormalForm),
+            Some(step)
+                if self
+                    .options
+                    .max_term_size
+                    .is_some_and(|max| step.term.size() > max) =>
+            {
+                Some(Outcome::SizeLimitExceeded)
+            }
+            Some(_) => None,
+        };
+        self.count += 1;
+        let step = step.filter(|_| self.outcome.is_none())?;
+        if let Some(cycles) = &mut self.cycles {
+            match step.kind {
+                // α-conversions always lead to an α-equivalent term, which is not a cycle.
+                StepKind::Alpha => {}
+                // Repeating a side effect needn't repeat its result, e.g. reading input.
+                StepKind::SideEffect => {
+                    cycles.clear();
+                    cycles.visit(self.count, &step.term);
+                }
+                _ => {
+                    if let Some(cycle_start) = cycles.visit(self.count, &step.term) {
+                        self.outcome = Some(Outcome::Diverges {
+                            cycle_start,
+                            cycle_length: self.count - cycle_start,
+                        });
+                    }
+                }
+            }
+        }
+        Some(step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_normal_form() {
+        let mut evaluation = EvaluationOptions::default().evaluate(λ![(λx.x) X]);
+        assert_eq!(1, evaluation.by_ref().count());
+        assert_eq!(Some(Outcome::NormalForm), evaluation.outcome());
+    }
+
+    #[test]
+    fn reports_normal_form_reached_before_step_limit() {
+        let mut evaluation = options().with_max_steps(2).evaluate(λ![(λx.x) X]);
+        assert_eq!(1, evaluation.by_ref().count());
+        assert_eq!(Some(Outcome::NormalForm), evaluation.outcome());
+    }
+
+    #[test]
+    fn stops_at_step_limit() {
+        let mut evaluation = options().with_max_steps(10).evaluate(omega());
+        assert_eq!(10, evaluation.by_ref().count());
+        assert_eq!(Some(Outcome::StepLimitReached), evaluation.outcome());
+    }
+
+    #[test]
+    fn performs_no_side_effects_past_step_limit() {
+        let mut performed = 0;
+        let effects = |_, term: &Term<'static>| {
+            performed += 1;
+            Some(term.clone())
+        };
+        let mut evaluation = options()
+            .with_max_steps(2)
+            .evaluate_with_side_effects(λ![:a (:b (:c X))], effects);
+        assert_eq!(2, evaluation.by_ref().count());
+        assert_eq!(Some(Outcome::StepLimitReached), evaluation.outcome());
+        drop(evaluation);
+        assert_eq!(2, performed);
+    }
+
+    #[test]
+    fn stops_before_exceeding_term_size() {
+        let growing = λ![(λx.x x x) (λx.x x x)];
+        let mut evaluation = options().with_max_term_size(20).evaluate(growing);
+        assert!(evaluation.by_ref().all(|step| step.term.size() <= 20));
+        assert_eq!(Some(Outcome::SizeLimitExceeded), evaluation.outcome());
+    }
+
+    #[test]
+    fn stops_after_deadline() {
+        let mut evaluation = options().with_deadline(Instant::now()).evaluate(omega());
+        assert_eq!(0, evaluation.by_ref().count());
+        assert_eq!(Some(Outcome::DeadlineExceeded), evaluation.outcome());
+    }
+
+    #[test]
+    fn stops_when_cancelled() {
+        let token = CancellationToken::new();
+        let mut evaluation = options().with_cancellation(token.clone()).evaluate(omega());
+        assert_eq!(3, evaluation.by_ref().take(3).count());
+        token.cancel();
+        assert_eq!(0, evaluation.by_ref().count());
+        assert_eq!(Some(Outcome::Cancelled), evaluation.outcome());
+    }
+
+    #[test]
+    fn detects_cycle_once_term_repeats() {
+        let mut evaluation = options().with_cycle_detection().evaluate(omega());
+        assert_eq!(1, evaluation.by_ref().count());
+        let outcome = evaluation.outcome();
+        let expected = Outcome::Diverges {
+            cycle_start: 0,
+            cycle_length: 1,
+        };
+        assert_eq!(Some(expected), outcome);
+    }
+
+    #[test]
+    fn detects_cycle_that_does_not_start_at_initial_term() {
+        let term = app(λ![λy.y], omega());
+        let mut evaluation = options().with_cycle_detection().evaluate(term);
+        assert_eq!(2, evaluation.by_ref().count());
+        let expected = Outcome::Diverges {
+            cycle_start: 1,
+            cycle_length: 1,
+        };
+        assert_eq!(Some(expected), evaluation.outcome());
+    }
+
+    #[test]
+    fn does_not_mistake_alpha_conversion_for_cycle() {
+        let term = λ![(λx.λy.x y) y];
+        let mut evaluation = options().with_cycle_detection().evaluate(term);
+        let kinds: Vec<_> = evaluation.by_ref().map(|step| step.kind).collect();
+        assert!(kinds.contains(&StepKind::Alpha));
+        assert_eq!(Some(Outcome::NormalForm), evaluation.outcome());
+    }
+
+    #[test]
+    fn detects_cycles_longer_than_one_step() {
+        let term = λ![(λx.(λy.x x) z) (λx.(λy.x x) z)];
+        let mut evaluation = options().with_cycle_detection().evaluate(term);
+        assert_eq!(3, evaluation.by_ref().count());
+        let expected = Outcome::Diverges {
+            cycle_start: 1,
+            cycle_length: 2,
+        };
+        assert_eq!(Some(expected), evaluation.outcome());
+    }
+
+    #[test]
+    fn does_not_detect_cycles_across_side_effects() {
+        let term = λ![(λx.:s x x) (λx.:s x x)];
+        let effects = |_, term: &Term<'static>| Some(term.clone());
+        let mut evaluation = options()
+            .with_cycle_detection()
+            .evaluate_with_side_effects(term, effects);
+        let kinds: Vec<_> = evaluation.by_ref().take(10).map(|step| step.kind).collect();
+        assert!(kinds.contains(&StepKind::SideEffect), "{kinds:?}");
+        assert_eq!(None, evaluation.outcome());
+    }
+
+    #[test]
+    fn does_not_detect_cycles_unless_enabled() {
+        let mut evaluation = options().with_max_steps(10).evaluate(omega());
+        assert_eq!(10, evaluation.by_ref().count());
+        assert_eq!(Some(Outcome::StepLimitReached), evaluation.outcome());
+    }
+
+    #[test]
+    fn uses_configured_strategy() {
+        let evaluation = options()
+            .with_strategy(EvaluationStrategy::CallByName)
+            .evaluate(λ![λx.(λy.y) x]);
+        assert_eq!(0, evaluation.count());
+    }
+
+    fn options() -> EvaluationOptions {
+        EvaluationOptions::default()
+    }
+
+    fn omega() -> Term<'static> {
+        λ![(λx.x x) (λx.x x)]
+    }
+}
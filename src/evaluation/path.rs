@@ -0,0 +1,175 @@
+use super::*;
+use Direction::*;
+
+/// The position of a subterm as the directions that lead to it from the root of a term.
+///
+/// Example: `(λx.x y) z` has the subterm `y` at `[Left, Body, Right]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Path(Box<[Direction]>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// The body of an abstraction.
+    Body,
+    /// The left side of an application.
+    Left,
+    /// The right side of an application.
+    Right,
+}
+
+impl Path {
+    pub fn directions(&self) -> &[Direction] {
+        &self.0
+    }
+
+    /// Returns the subterm of `term` at this path.
+    pub fn get<'t, 'a>(&self, term: &'t Term<'a>) -> Option<&'t Term<'a>> {
+        self.0
+            .iter()
+            .try_fold(term, |term, direction| match (direction, term) {
+                (Body, Term::Abs(abs)) => Some(&abs.term),
+                (Left, Term::App(app)) => Some(&app.left),
+                (Right, Term::App(app)) => Some(&app.right),
+                _ => None,
+            })
+    }
+
+    pub(crate) fn prepend(&mut self, directions: &[Direction]) {
+        self.0 = [directions, &self.0].concat().into();
+    }
+}
+
+impl From<Vec<Direction>> for Path {
+    fn from(directions: Vec<Direction>) -> Self {
+        Path(directions.into())
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('[')?;
+        for (index, direction) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{direction:?}")?;
+        }
+        f.write_char(']')
+    }
+}
+
+impl<'a> Term<'a> {
+    /// Displays this term with subterms wrapped in markers added through
+    /// [`Highlighted::with_highlight`] (e.g. ANSI escape codes).
+    pub fn highlighted<'t>(&'t self) -> Highlighted<'t, 'a> {
+        Highlighted {
+            term: self,
+            highlights: Vec::new(),
+        }
+    }
+}
+
+/// A term that is displayed with some of its subterms highlighted, see [`Term::highlighted`].
+#[derive(Debug, Clone)]
+pub struct Highlighted<'t, 'a> {
+    term: &'t Term<'a>,
+    highlights: Vec<(&'t Path, &'t str, &'t str)>,
+}
+
+impl<'t, 'a> Highlighted<'t, 'a> {
+    /// Writes `before` and `after` around the subterm at `path`.
+    /// Highlights may be nested, so `after` should only undo what `before` did.
+    pub fn with_highlight(mut self, path: &'t Path, before: &'t str, after: &'t str) -> Self {
+        self.highlights.push((path, before, after));
+        self
+    }
+
+    fn fmt_at(
+        &self,
+        term: &Term,
+        position: &mut Vec<Direction>,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        let highlights: Vec<_> = self
+            .highlights
+            .iter()
+            .filter(|(path, ..)| *path.0 == **position)
+            .collect();
+        highlights
+            .iter()
+            .try_for_each(|(_, before, _)| f.write_str(before))?;
+        match term {
+            Term::Var(variable) => write!(f, "{variable}")?,
+            Term::Abs(abs) => {
+                write!(f, "λ{}.", abs.variable)?;
+                self.fmt_below(&abs.term, Body, position, f)?;
+            }
+            Term::App(app) => {
+                with_parenthesis(matches!(app.left, Term::Abs(_)), f, |f| {
+                    self.fmt_below(&app.left, Left, position, f)
+                })?;
+                f.write_char(' ')?;
+                with_parenthesis(matches!(app.right, Term::App(_) | Term::Abs(_)), f, |f| {
+                    self.fmt_below(&app.right, Right, position, f)
+                })?;
+            }
+        }
+        highlights
+            .iter()
+            .rev()
+            .try_for_each(|(_, _, after)| f.write_str(after))
+    }
+
+    fn fmt_below(
+        &self,
+        term: &Term,
+        direction: Direction,
+        position: &mut Vec<Direction>,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        position.push(direction);
+        let result = self.fmt_at(term, position, f);
+        position.pop();
+        result
+    }
+}
+
+impl<'t, 'a> fmt::Display for Highlighted<'t, 'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_at(self.term, &mut Vec::new(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_subterm_at_path() {
+        let term = λ![(λx.x y) z];
+        let path = Path::from(vec![Left, Body, Right]);
+        assert_eq!(Some(&var("y")), path.get(&term));
+        assert_eq!(Some(&term), Path::default().get(&term));
+        assert_eq!(None, Path::from(vec![Body]).get(&term));
+    }
+
+    #[test]
+    fn wraps_highlighted_subterms_in_markers() {
+        let term = λ![(λx.x y) (z z)];
+        let function = Path::from(vec![Left]);
+        let argument = Path::from(vec![Right]);
+        let variable = Path::from(vec![Left, Body, Left]);
+        let highlighted = term
+            .highlighted()
+            .with_highlight(&function, "<", ">")
+            .with_highlight(&variable, "[", "]")
+            .with_highlight(&argument, "{", "}");
+        assert_eq!("(<λx.[x] y>) ({z z})", highlighted.to_string());
+    }
+
+    #[test]
+    fn displays_term_without_highlights_as_usual() {
+        let term = λ![(λx.x y) (z (λw.w))];
+        assert_eq!(term.to_string(), term.highlighted().to_string());
+    }
+}
@@ -1,12 +1,16 @@
 use super::*;
 use Term::*;
 
-/// Renames a free variable in the given term.
+/// Renames a free variable in the given term. Renamed occurrences keep their span.
 pub fn rename<'a>(old: Variable<'a>, new: Variable<'a>, term: Term<'a>) -> Term<'a> {
     match term {
-        Var(var) if var == old => new.into(),
-        Abs! { variable, term } if variable != old => abs(variable, rename(old, new, term)),
-        App! { left, right } => app(rename(old, new, left), rename(old, new, right)),
+        Var(var) if var == old => new.with_span(var.span).into(),
+        Abs! { variable, term, span } if variable != old => {
+            abs(variable, rename(old, new, term)).with_span(span)
+        }
+        App! { left, right, span } => {
+            app(rename(old, new, left), rename(old, new, right)).with_span(span)
+        }
         term => term,
     }
 }
@@ -30,6 +34,18 @@ mod tests {
         assert_eq!(term.clone(), rename(old, new, term));
     }
 
+    #[test]
+    fn keeps_span_of_renamed_occurrence() {
+        let old = Variable::new("x");
+        let new = old.with_disambiguator(1).with_span(Some(Span::from(0..1)));
+        let renamed = rename(old, new, parse_term("y   x"));
+        assert_eq!(app(var("y"), new), renamed);
+        let App! { right } = renamed else {
+            unreachable!()
+        };
+        assert_eq!(Some(Span::from(4..5)), right.span());
+    }
+
     #[test]
     fn renames_matching_variables_in_application() {
         let old = Variable::new("x");
@@ -10,35 +10,59 @@ trait_set! {
 /// Renames all bound variables in the given term to fit a given predicate.
 pub fn rename_bound(term: Term<'_>, predicate: impl RenameBoundPredicate) -> TermResult<'_> {
     match term {
-        Abs! { variable, term } if !predicate(&variable) => {
+        Abs! { variable, term, span } if !predicate(&variable) => {
             let new_variable = new_variable_for_term(variable, &term, predicate.clone());
             let renamed = rename(variable, new_variable, term);
-            Modified(abs(new_variable, rename_bound(renamed, predicate).term()))
+            Modified(abs(new_variable, rename_bound(renamed, predicate).term()).with_span(span))
         }
-        Abs! { variable, term } => rename_bound(term, predicate).map(|term| abs(variable, term)),
-        App! { left, right } => TermResult::map2(
+        Abs! { variable, term, span } => {
+            rename_bound(term, predicate).map(|term| abs(variable, term).with_span(span))
+        }
+        App! { left, right, span } => TermResult::map2(
             rename_bound(left, predicate.clone()),
             rename_bound(right, predicate),
-            app,
+            |left, right| app(left, right).with_span(span),
         ),
         term @ Var(_) => Original(term),
     }
 }
 
+/// Renames only the variable of the abstraction `λvariable.term` to one that fits the
+/// predicate, unlike [`rename_bound`] which renames every bound variable that doesn't.
+pub(crate) fn rename_variable<'a>(
+    variable: Variable<'a>,
+    term: Term<'a>,
+    predicate: impl RenameBoundPredicate,
+) -> Term<'a> {
+    let new_variable = new_variable_for_term(variable, &term, predicate);
+    abs(new_variable, rename(variable, new_variable, term))
+}
+
 fn new_variable_for_term<'a>(
     variable: Variable<'a>,
     term: &Term<'a>,
     predicate: impl RenameBoundPredicate,
 ) -> Variable<'a> {
     let is_free = is_free_in(term);
-    new_variable(variable, |v| predicate(v) && !is_free(v))
+    new_variable(variable, |v| {
+        predicate(v) && !is_free(v) && !is_binder_in(term, v)
+    })
 }
 
-fn new_variable(variable: Variable, predicate: impl Fn(&Variable) -> bool) -> Variable {
+/// Renaming to a variable that is bound further down would capture the renamed occurrences.
+fn is_binder_in(term: &Term, variable: &Variable) -> bool {
+    match term {
+        Abs! { variable: bound, term } => bound == variable || is_binder_in(term, variable),
+        App! { left, right } => is_binder_in(left, variable) || is_binder_in(right, variable),
+        Var(_) => false,
+    }
+}
+
+pub(crate) fn new_variable(variable: Variable, predicate: impl Fn(&Variable) -> bool) -> Variable {
     (1..)
         .take(if cfg!(test) { 1000 } else { usize::MAX })
         .filter(|d| Disambiguator::Numeric(*d) != variable.disambiguator)
-        .map(|d| Variable::new(variable.name).with_disambiguator(d))
+        .map(|d| variable.with_disambiguator(d))
         .find(predicate)
         .expect("No more disambiguators left, what are you doing?")
 }
@@ -111,6 +135,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn skips_variables_bound_in_abstraction_term_when_renaming() {
+        let expected = abs(("x", 2), abs(("x", 1), var_with("x", 2)));
+        let term = abs("x", abs(("x", 1), var("x")));
+        assert_eq!(
+            Modified(expected),
+            rename_bound(term, |v| v != &Variable::new("x"))
+        );
+    }
+
+    #[test]
+    fn keeps_spans_of_renamed_binder_and_occurrences() {
+        let renamed = rename_bound(parse_term(r"\x.x"), |v| v != &Variable::new("x")).term();
+        let Abs! { variable, term, span } = renamed else {
+            unreachable!()
+        };
+        assert_eq!(Some(Span::from(0..4)), span);
+        assert_eq!(Some(Span::from(1..2)), variable.span);
+        assert_eq!(Some(Span::from(3..4)), term.span());
+    }
+
     #[test]
     #[should_panic(expected = "No more disambiguators left")]
     fn panics_when_all_disambiguators_are_used_up() {
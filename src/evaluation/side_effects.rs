@@ -1,5 +1,137 @@
-use crate::evaluation::{evaluate, Step, Term};
+use super::*;
+use Term::*;
 
-pub fn evaluate_with_side_effects<'a>(term: impl Into<Term<'a>>) -> impl Iterator<Item = Step<'a>> {
-    evaluate(term)
+/// Performs the side effect associated with a symbol (e.g. `:write`)
+/// when that symbol is applied to an argument in normal form.
+pub trait EffectHandler<'a> {
+    /// Returns the term that replaces the application of `symbol` to `argument`
+    /// or `None` if the symbol has no side effect associated with it.
+    fn perform(&mut self, symbol: &'a str, argument: &Term<'a>) -> Option<Term<'a>>;
+}
+
+impl<'a, F> EffectHandler<'a> for F
+where
+    F: FnMut(&'a str, &Term<'a>) -> Option<Term<'a>>,
+{
+    fn perform(&mut self, symbol: &'a str, argument: &Term<'a>) -> Option<Term<'a>> {
+        self(symbol, argument)
+    }
+}
+
+/// An [`EffectHandler`] that doesn't know of any side effects.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoSideEffects;
+
+impl<'a> EffectHandler<'a> for NoSideEffects {
+    fn perform(&mut self, _symbol: &'a str, _argument: &Term<'a>) -> Option<Term<'a>> {
+        None
+    }
+}
+
+/// Evaluates a program's term, consulting `effects` whenever a symbol
+/// in head position is applied to an argument in normal form.
+pub fn evaluate_with_side_effects<'a>(
+    program: impl Into<Program<'a>>,
+    effects: impl EffectHandler<'a>,
+) -> impl Iterator<Item = Step<'a>> {
+    EvaluationStrategy::NormalOrder.evaluate_with_side_effects(program, effects)
+}
+
+pub(crate) fn perform<'a>(
+    left: Term<'a>,
+    right: Term<'a>,
+    span: Option<Span>,
+    environment: &mut Environment<'a, impl EffectHandler<'a>>,
+) -> Step<'a> {
+    if let Var(Variable {
+        name,
+        disambiguator: Disambiguator::Symbol,
+        ..
+    }) = left
+    {
+        if let Some(term) = environment.effects.perform(name, &right) {
+            return Step::new(StepKind::SideEffect, term).at_redex(span);
+        }
+    }
+    Step::new(StepKind::Id, app(left, right).with_span(span))
+}
+
+/// Reduces the argument of a symbol with `reduce` first, for strategies
+/// that don't reduce arguments on their own.
+pub(crate) fn perform_with<'a, E: EffectHandler<'a>>(
+    symbol: Term<'a>,
+    argument: Term<'a>,
+    span: Option<Span>,
+    environment: &mut Environment<'a, E>,
+    reduce: impl FnOnce(Term<'a>, &mut Environment<'a, E>) -> Step<'a>,
+) -> Step<'a> {
+    match reduce(argument, environment).id_or_err() {
+        Ok(argument) => perform(symbol, argument, span, environment),
+        Err(argument) => argument.map(&[Direction::Right], |argument| {
+            app(symbol, argument).with_span(span)
+        }),
+    }
+}
+
+pub(crate) fn is_symbol(term: &Term<'_>) -> bool {
+    matches!(
+        term,
+        Var(Variable {
+            disambiguator: Disambiguator::Symbol,
+            ..
+        })
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn performs_side_effect_of_symbol_in_head_position() {
+        let steps: Vec<_> = evaluate_with_side_effects(λ![:id X], identity).collect();
+        assert_eq!(vec![Step::new(StepKind::SideEffect, λ![X])], steps);
+    }
+
+    #[test]
+    fn reduces_argument_to_normal_form_before_performing_side_effect() {
+        let mut arguments = Vec::new();
+        let effects = |_, argument: &Term<'static>| {
+            arguments.push(argument.clone());
+            Some(var("done"))
+        };
+        let kinds: Vec<_> = evaluate_with_side_effects(λ![:write ((λx.x) X)], effects)
+            .map(|step| step.kind)
+            .collect();
+        assert_eq!(vec![StepKind::Beta, StepKind::SideEffect], kinds);
+        assert_eq!(vec![λ![X]], arguments);
+    }
+
+    #[test]
+    fn passes_symbol_name_to_effect_handler() {
+        let mut symbols = Vec::new();
+        let effects = |symbol, _: &Term<'static>| {
+            symbols.push(symbol);
+            None
+        };
+        evaluate_with_side_effects(λ![:beep X], effects).for_each(drop);
+        assert_eq!(vec!["beep"], symbols);
+    }
+
+    #[test]
+    fn ignores_symbols_without_side_effects() {
+        assert_eq!(
+            0,
+            evaluate_with_side_effects(λ![:n X], NoSideEffects).count()
+        );
+    }
+
+    #[test]
+    fn does_not_perform_side_effects_for_variables() {
+        assert_eq!(0, evaluate_with_side_effects(λ![id X], identity).count());
+    }
+
+    fn identity<'a>(_symbol: &'a str, argument: &Term<'a>) -> Option<Term<'a>> {
+        Some(argument.clone())
+    }
 }
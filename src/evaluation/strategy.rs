@@ -0,0 +1,194 @@
+use super::*;
+use std::error::Error;
+use std::str::FromStr;
+use EvaluationStrategy::*;
+
+/// The order in which redexes are contracted and when evaluation stops.
+///
+/// Strategies only differ in which terms they reach, e.g. `(λx.y) Ω` has a normal
+/// form under [`NormalOrder`] but diverges under [`ApplicativeOrder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum EvaluationStrategy {
+    /// Leftmost-outermost reduction to normal form.
+    #[default]
+    NormalOrder,
+    /// Leftmost-innermost reduction to normal form.
+    ApplicativeOrder,
+    /// Reduction to weak head normal form without evaluating arguments.
+    CallByName,
+    /// Reduction to weak normal form, evaluating arguments before substituting them.
+    CallByValue,
+    /// Reduction to head normal form, i.e. also below abstractions but never in arguments.
+    Head,
+    /// Call-by-name with sharing of evaluated arguments (see [`evaluate_by_need`]).
+    CallByNeed,
+}
+
+impl EvaluationStrategy {
+    /// Every strategy, in the order they're declared.
+    pub const ALL: [EvaluationStrategy; 6] = [
+        NormalOrder,
+        ApplicativeOrder,
+        CallByName,
+        CallByValue,
+        Head,
+        CallByNeed,
+    ];
+
+    /// Evaluates a program's term, expanding the program's definitions as they're needed.
+    pub fn evaluate<'a>(self, program: impl Into<Program<'a>>) -> impl Iterator<Item = Step<'a>> {
+        self.evaluate_with_side_effects(program, NoSideEffects)
+    }
+
+    /// Evaluates a program's term, consulting `effects` whenever a symbol
+    /// in head position is applied to an argument that can't be reduced any further.
+    pub fn evaluate_with_side_effects<'a>(
+        self,
+        program: impl Into<Program<'a>>,
+        effects: impl EffectHandler<'a>,
+    ) -> impl Iterator<Item = Step<'a>> {
+        evaluate_in(program.into(), effects, move |term, environment| {
+            self.reduce(term, environment)
+        })
+    }
+
+    fn reduce<'a>(
+        self,
+        term: Term<'a>,
+        environment: &mut Environment<'a, impl EffectHandler<'a>>,
+    ) -> Step<'a> {
+        match self {
+            NormalOrder => beta::reduce(term, environment),
+            ApplicativeOrder => applicative_order::reduce(term, environment),
+            CallByName => call_by_name::reduce(term, environment),
+            CallByValue => call_by_value::reduce(term, environment),
+            Head => head::reduce(term, environment),
+            CallByNeed => call_by_need::reduce(term, environment),
+        }
+    }
+}
+
+impl fmt::Display for EvaluationStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NormalOrder => f.write_str("normal-order"),
+            ApplicativeOrder => f.write_str("applicative-order"),
+            CallByName => f.write_str("call-by-name"),
+            CallByValue => f.write_str("call-by-value"),
+            Head => f.write_str("head"),
+            CallByNeed => f.write_str("call-by-need"),
+        }
+    }
+}
+
+impl FromStr for EvaluationStrategy {
+    type Err = UnknownStrategyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|strategy| strategy.to_string() == s)
+            .ok_or_else(|| UnknownStrategyError(s.to_owned()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownStrategyError(pub String);
+
+impl fmt::Display for UnknownStrategyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown evaluation strategy `{}`", self.0)
+    }
+}
+
+impl Error for UnknownStrategyError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIVERGES: usize = 100;
+
+    #[test]
+    fn only_strict_strategies_diverge_on_discarded_argument() {
+        let expression = λ![(λx.Y) ((λx.x x) (λx.x x))];
+        for strategy in EvaluationStrategy::ALL {
+            let steps: Vec<_> = strategy
+                .evaluate(expression.clone())
+                .take(DIVERGES)
+                .collect();
+            match strategy {
+                ApplicativeOrder | CallByValue => assert_eq!(DIVERGES, steps.len(), "{strategy}"),
+                _ => assert_eq!(λ![Y], steps.last().unwrap().term, "{strategy}"),
+            }
+        }
+    }
+
+    #[test]
+    fn only_strong_strategies_reduce_below_abstractions() {
+        let expression = λ![λz.(λx.x) z];
+        for strategy in EvaluationStrategy::ALL {
+            let normal_form = strategy.evaluate(expression.clone()).last();
+            match strategy {
+                CallByName | CallByValue => assert_eq!(None, normal_form, "{strategy}"),
+                _ => assert_eq!(λ![λz.z], normal_form.unwrap().term, "{strategy}"),
+            }
+        }
+    }
+
+    #[test]
+    fn call_by_name_and_head_reduction_leave_arguments_of_free_variables_alone() {
+        let expression = λ![X ((λx.x) Y)];
+        for strategy in EvaluationStrategy::ALL {
+            let normal_form = strategy.evaluate(expression.clone()).last();
+            match strategy {
+                CallByName | Head => assert_eq!(None, normal_form, "{strategy}"),
+                _ => assert_eq!(λ![X Y], normal_form.unwrap().term, "{strategy}"),
+            }
+        }
+    }
+
+    #[test]
+    fn applicative_order_takes_fewer_steps_for_duplicated_arguments() {
+        let expression = λ![(λx.x x x) ((λy.y) Z)];
+        let normal_order = NormalOrder.evaluate(expression.clone()).count();
+        let applicative_order = ApplicativeOrder.evaluate(expression).count();
+        assert_eq!((4, 2), (normal_order, applicative_order));
+    }
+
+    #[test]
+    fn records_paths_of_contracted_redexes() {
+        let expressions = [
+            λ![(λx.λf.f x x) ((λy.y) (λz.z))],
+            λ![(λa.(λb.λc.c a b) (λd.d)) ((λe.e) (λg.g))],
+            λ![X ((λx.λy.y x) Y)],
+        ];
+        for strategy in EvaluationStrategy::ALL {
+            for expression in &expressions {
+                let mut before = expression.clone();
+                for step in strategy.evaluate(expression.clone()) {
+                    let redex = step.redex_path.unwrap().get(&before).cloned();
+                    if step.kind == StepKind::Beta {
+                        assert!(
+                            matches!(redex, Some(Term::App(app)) if matches!(app.left, Term::Abs(_))),
+                            "{strategy}: {before}"
+                        );
+                    }
+                    assert!(step.contractum_path.unwrap().get(&step.term).is_some());
+                    before = step.term;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parses_displayed_strategies() {
+        for strategy in EvaluationStrategy::ALL {
+            assert_eq!(Ok(strategy), strategy.to_string().parse());
+        }
+        assert_eq!(
+            Err(UnknownStrategyError("eager".to_owned())),
+            "eager".parse::<EvaluationStrategy>()
+        );
+    }
+}
@@ -11,11 +11,14 @@ pub fn substitute<'a>(needle: Variable, replacement: &Term<'a>, input: Term<'a>)
         Var(v) if v == needle => replacement.clone(),
         term @ Var(_) => term,
         Abs(a) if a.variable == needle => Abs(a),
-        Abs! { variable, term } => abs(variable, substitute(needle, replacement, term)),
-        App! { left, right } => app(
+        Abs! { variable, term, span } => {
+            abs(variable, substitute(needle, replacement, term)).with_span(span)
+        }
+        App! { left, right, span } => app(
             substitute(needle, replacement, left),
             substitute(needle, replacement, right),
-        ),
+        )
+        .with_span(span),
     }
 }
 
@@ -53,6 +56,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn keeps_spans_of_replacement_and_input() {
+        let input = parse_term(r"\y.y x");
+        let replacement = var("R").with_span(Some(Span::from(10..11)));
+        let substituted = substitute(Variable::new("x"), &replacement, input);
+        assert_eq!(Some(Span::from(0..6)), substituted.span());
+        let Abs! { term: App! { right } } = substituted else {
+            unreachable!()
+        };
+        assert_eq!(Some(Span::from(10..11)), right.span());
+    }
+
     #[test]
     fn does_not_substitutes_non_matching_free_variable() {
         let variable = Variable::new("x");
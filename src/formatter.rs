@@ -0,0 +1,649 @@
+use crate::diagnostics::*;
+use crate::parsers::*;
+use crate::syntax::*;
+use std::borrow::Cow;
+use std::iter;
+use std::ops::Range;
+
+/// Formats a program, or a file with only imports and definitions, see [`FormatOptions::format`].
+pub fn format(input: &str) -> DiagnosticsResult<String> {
+    FormatOptions::default().format(input)
+}
+
+/// Changes how [`FormatOptions::format`] lays out the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOptions {
+    width: usize,
+    parse_options: ParseOptions,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            width: 80,
+            parse_options: ParseOptions::default(),
+        }
+    }
+}
+
+impl FormatOptions {
+    /// The number of characters after which lines are wrapped if possible, 80 by default.
+    pub fn with_width(self, width: usize) -> Self {
+        FormatOptions { width, ..self }
+    }
+
+    /// Parses the input with the given options.
+    pub fn with_parse_options(self, parse_options: ParseOptions) -> Self {
+        FormatOptions {
+            parse_options,
+            ..self
+        }
+    }
+
+    /// Prints the input in the same way regardless of how it was written, keeping its
+    /// comments and the empty lines between definitions. The result parses to the same syntax.
+    ///
+    /// Nested abstractions are written as `λx y.body` and there are only the parentheses
+    /// that are needed, and the ones around abstractions and bindings that are arguments.
+    /// Formulas that don't fit into the width are broken into lines, e.g. with the arguments
+    /// of an application indented below its function.
+    ///
+    /// Files without a formula are formatted as modules, the error is the one of parsing
+    /// them as a program.
+    pub fn format(self, input: &str) -> DiagnosticsResult<String> {
+        let output = match self.parse_options.parse_program_with_comments(input) {
+            Ok(program) => {
+                let (program, comments) = program.value;
+                let mut items = items(&program.imports, &program.definitions);
+                items.push(Item::Formula(&program.formula));
+                Formatter::new(input, comments, self).items(&items)
+            }
+            Err(errors) => match self.parse_options.parse_module_with_comments(input) {
+                Ok(module) => {
+                    let (module, comments) = module.value;
+                    let items = items(&module.imports, &module.definitions);
+                    Formatter::new(input, comments, self).items(&items)
+                }
+                Err(_) => return Err(errors),
+            },
+        };
+        Ok(WithDiagnostics::with_empty_diagnostics(output))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Item<'p, 'a> {
+    Import(&'p Import<'a>),
+    Definition(&'p NominalDefinition<'a>),
+    Formula(&'p Formula<'a>),
+}
+
+impl Item<'_, '_> {
+    fn span(self) -> Span {
+        match self {
+            Item::Import(import) => import.span,
+            Item::Definition(definition) => definition.span,
+            Item::Formula(formula) => *formula.span(),
+        }
+    }
+}
+
+/// The imports and definitions in the order they were written in.
+fn items<'p, 'a>(
+    imports: &'p [Import<'a>],
+    definitions: &'p [NominalDefinition<'a>],
+) -> Vec<Item<'p, 'a>> {
+    let imports = imports.iter().map(Item::Import);
+    let mut items: Vec<_> = imports
+        .chain(definitions.iter().map(Item::Definition))
+        .collect();
+    items.sort_by_key(|item| item.span().start);
+    items
+}
+
+/// Whether a formula is written starting with a variable `import` applied to a string,
+/// like an import.
+fn starts_like_import(formula: &Formula<'_>) -> bool {
+    match formula {
+        Formula::App(app) => match (&app.left, &app.right) {
+            (Formula::Var(var), Formula::Str(_)) => var.value == "import",
+            (left, _) => starts_like_import(left),
+        },
+        Formula::Let(binding) if binding.style == LetStyle::Where => {
+            starts_like_import(&binding.body)
+        }
+        _ => false,
+    }
+}
+
+/// A layout of text that is broken into lines where it doesn't fit, see [`Printer`].
+#[derive(Debug)]
+enum Doc<'s> {
+    Text(Cow<'s, str>),
+    /// A space, or a line break if the enclosing group doesn't fit.
+    Line,
+    /// Nothing, or a line break if the enclosing group doesn't fit.
+    Break,
+    /// Parts whose line breaks are indented.
+    Nest(Vec<Doc<'s>>),
+    /// Parts that are on one line if they fit, otherwise all of their own line breaks are.
+    Group(Vec<Doc<'s>>),
+    Concat(Vec<Doc<'s>>),
+    /// A comment that is followed by a line break.
+    Comment(Comment<'s>),
+}
+
+impl<'s> Doc<'s> {
+    fn text(text: impl Into<Cow<'s, str>>) -> Self {
+        Doc::Text(text.into())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Comment<'s> {
+    text: &'s str,
+    /// Whether the comment was on a line of its own, rather than after something else.
+    own_line: bool,
+    /// Whether there was an empty line before the comment.
+    spaced: bool,
+}
+
+/// Where a formula is, which decides whether it has to be parenthesized.
+#[derive(Clone, Copy)]
+struct Position {
+    /// Whether an application is one formula of another application here.
+    argument: bool,
+    /// Whether a `where` binding would take the rest of a `where` binding here.
+    where_value: bool,
+    /// Whether an abstraction or `let` could go on until the end of the formula here.
+    open: bool,
+}
+
+impl Position {
+    /// Anywhere nothing follows, e.g. in parentheses.
+    const OPEN: Position = Position {
+        argument: false,
+        where_value: false,
+        open: true,
+    };
+    const FUNCTION: Position = Position {
+        argument: false,
+        where_value: true,
+        open: false,
+    };
+    const ARGUMENT: Position = Position {
+        argument: true,
+        where_value: true,
+        open: false,
+    };
+    const WHERE_BODY: Position = Position {
+        argument: false,
+        where_value: false,
+        open: false,
+    };
+
+    fn parenthesizes(self, formula: &Formula<'_>) -> bool {
+        match formula {
+            Formula::App(_) => self.argument,
+            Formula::Let(binding) if binding.style == LetStyle::Where => {
+                self.argument || self.where_value
+            }
+            Formula::Abs(_) | Formula::Let(_) => !self.open,
+            _ => false,
+        }
+    }
+}
+
+const INDENT: usize = 2;
+
+struct Formatter<'s> {
+    source: &'s str,
+    /// The comments that haven't been formatted yet, in reverse.
+    comments: Vec<Span>,
+    options: FormatOptions,
+}
+
+impl<'s> Formatter<'s> {
+    fn new(source: &'s str, mut comments: Vec<Span>, options: FormatOptions) -> Self {
+        comments.reverse();
+        Formatter {
+            source,
+            comments,
+            options,
+        }
+    }
+
+    fn items(mut self, items: &[Item<'_, 's>]) -> String {
+        let mut output = String::new();
+        for item in items {
+            let span = item.span();
+            self.top_level_comments(span.start, &mut output);
+            if self.spaced(span.start) && !output.is_empty() {
+                output.push('\n');
+            }
+            let doc = match item {
+                Item::Import(import) => Doc::text(import.to_string()),
+                Item::Definition(definition) => self.definition(definition),
+                // Parenthesized so that it isn't read as an import
+                Item::Formula(formula) if starts_like_import(formula) => {
+                    let doc = self.formula(formula, Position::OPEN);
+                    Doc::Concat(vec![Doc::text("("), doc, Doc::text(")")])
+                }
+                Item::Formula(formula) => self.formula(formula, Position::OPEN),
+            };
+            output.push_str(&Printer::new(self.options.width).print(&doc));
+            output.push('\n');
+        }
+        self.top_level_comments(usize::MAX, &mut output);
+        output
+    }
+
+    /// Writes the comments before `position` on lines of their own, unless they were
+    /// after the previous item.
+    fn top_level_comments(&mut self, position: usize, output: &mut String) {
+        for comment in self.comments(position) {
+            if comment.own_line || output.is_empty() {
+                if comment.spaced && !output.is_empty() {
+                    output.push('\n');
+                }
+            } else {
+                output.pop();
+                output.push(' ');
+            }
+            output.push_str(comment.text);
+            output.push('\n');
+        }
+    }
+
+    /// The comments before `position` that haven't been formatted yet.
+    fn comments(&mut self, position: usize) -> Vec<Comment<'s>> {
+        let mut comments = Vec::new();
+        while let Some(span) = self.comments.pop() {
+            if span.start >= position {
+                self.comments.push(span);
+                break;
+            }
+            let before = &self.source[..span.start];
+            let line = &before[before.rfind('\n').map_or(0, |index| index + 1)..];
+            comments.push(Comment {
+                text: self.source[Range::from(span)].trim_end(),
+                own_line: line.trim().is_empty(),
+                spaced: self.spaced(span.start),
+            });
+        }
+        comments
+    }
+
+    fn comment_docs(&mut self, position: usize) -> impl Iterator<Item = Doc<'s>> {
+        self.comments(position).into_iter().map(Doc::Comment)
+    }
+
+    /// Whether there is an empty line right before `position`.
+    fn spaced(&self, position: usize) -> bool {
+        let before = &self.source[..position];
+        let whitespace = &before[before.trim_end().len()..];
+        whitespace.matches('\n').count() > 1
+    }
+
+    fn definition(&mut self, definition: &NominalDefinition<'s>) -> Doc<'s> {
+        // Comments before the parentheses go on their own lines above the definition
+        let opening = self.opening_parenthesis(definition);
+        let mut parts: Vec<_> = self.comment_docs(opening).collect();
+        let name = self.name(&definition.name);
+        parts.push(Doc::text(format!("{name} -> (")));
+        parts.extend(self.comment_docs(definition.formula.span().start));
+        parts.push(self.formula(&definition.formula, Position::OPEN));
+        parts.extend(self.comment_docs(definition.span.end));
+        parts.push(Doc::text(")"));
+        Doc::Concat(parts)
+    }
+
+    /// Where the `(` after the `->` of `definition` is.
+    fn opening_parenthesis(&self, definition: &NominalDefinition<'_>) -> usize {
+        let start = definition.name.span.end;
+        let mut in_comment = false;
+        for (index, c) in self.source[start..].char_indices() {
+            match c {
+                '#' => in_comment = true,
+                '\n' => in_comment = false,
+                '(' if !in_comment => return start + index,
+                _ => {}
+            }
+        }
+        unreachable!("Definitions are parenthesized")
+    }
+
+    /// A name as it has to be written to be parsed with the parse options.
+    fn name(&self, identifier: &Identifier<'s>) -> Cow<'s, str> {
+        identifier.escaped(self.options.parse_options)
+    }
+
+    fn formula(&mut self, formula: &Formula<'s>, position: Position) -> Doc<'s> {
+        let parenthesized = position.parenthesizes(formula);
+        let doc = match formula {
+            Formula::Abs(abs) => self.abstraction(abs),
+            Formula::App(app) => self.application(app),
+            Formula::Let(binding) => self.binding(binding, position.open || parenthesized),
+            Formula::List(list) => self.list(list),
+            Formula::Var(var) => Doc::text(self.name(var)),
+            Formula::Sym(sym) => Doc::text(format!(":{}", self.name(&sym.ident))),
+            Formula::Num(num) => Doc::text(num.to_string()),
+            Formula::Str(text) => Doc::text(text.to_string()),
+            Formula::Error(_) => Doc::text(formula.to_string()),
+        };
+        match parenthesized {
+            true => Doc::Concat(vec![Doc::text("("), doc, Doc::text(")")]),
+            false => doc,
+        }
+    }
+
+    fn abstraction(&mut self, mut abs: &Abstraction<'s>) -> Doc<'s> {
+        let mut variables = vec![self.name(&abs.variable)];
+        while let Formula::Abs(inner) = &abs.formula {
+            variables.push(self.name(&inner.variable));
+            abs = inner;
+        }
+        let mut body: Vec<_> = self.comment_docs(abs.formula.span().start).collect();
+        body.push(Doc::Break);
+        body.push(self.formula(&abs.formula, Position::OPEN));
+        Doc::Group(vec![
+            Doc::text(format!("λ{}.", variables.join(" "))),
+            Doc::Nest(body),
+        ])
+    }
+
+    fn application(&mut self, mut app: &Application<'s>) -> Doc<'s> {
+        let mut arguments = vec![&app.right];
+        while let Formula::App(left) = &app.left {
+            arguments.push(&left.right);
+            app = left;
+        }
+        let function = self.formula(&app.left, Position::FUNCTION);
+        let mut rest = Vec::new();
+        for argument in arguments.into_iter().rev() {
+            rest.extend(self.comment_docs(argument.span().start));
+            rest.push(Doc::Line);
+            rest.push(self.formula(argument, Position::ARGUMENT));
+        }
+        Doc::Group(vec![function, Doc::Nest(rest)])
+    }
+
+    /// A `let` or `where` binding, whose last formula may go on until the end if it's `open`.
+    fn binding(&mut self, binding: &Let<'s>, open: bool) -> Doc<'s> {
+        let rec = if binding.recursive { "rec " } else { "" };
+        let name = self.name(&binding.name);
+        match binding.style {
+            LetStyle::Let => {
+                let mut value: Vec<_> = self.comment_docs(binding.value.span().start).collect();
+                value.push(Doc::Line);
+                value.push(self.formula(&binding.value, Position::OPEN));
+                let mut parts = vec![
+                    Doc::Group(vec![
+                        Doc::text(format!("let {rec}{name} =")),
+                        Doc::Nest(value),
+                    ]),
+                    Doc::text(" in"),
+                ];
+                parts.extend(self.comment_docs(binding.body.span().start));
+                parts.push(Doc::Line);
+                parts.push(self.formula(&binding.body, Position::OPEN));
+                Doc::Group(parts)
+            }
+            LetStyle::Where => {
+                let body = self.formula(&binding.body, Position::WHERE_BODY);
+                let mut clause: Vec<_> = self.comment_docs(binding.value.span().start).collect();
+                let value = Position {
+                    argument: false,
+                    where_value: true,
+                    open,
+                };
+                clause.push(Doc::Line);
+                clause.push(Doc::Group(vec![
+                    Doc::text(format!("where {rec}{name} =")),
+                    Doc::Nest(vec![Doc::Line, self.formula(&binding.value, value)]),
+                ]));
+                Doc::Group(vec![body, Doc::Nest(clause)])
+            }
+        }
+    }
+
+    fn list(&mut self, list: &List<'s>) -> Doc<'s> {
+        let mut items = Vec::new();
+        for (index, item) in list.items.iter().enumerate() {
+            if index > 0 {
+                items.push(Doc::text(","));
+            }
+            items.extend(self.comment_docs(item.span().start));
+            items.push(if index > 0 { Doc::Line } else { Doc::Break });
+            items.push(self.formula(item, Position::OPEN));
+        }
+        items.extend(self.comment_docs(list.span.end));
+        if items.is_empty() {
+            return Doc::text("[]");
+        }
+        Doc::Group(vec![
+            Doc::text("["),
+            Doc::Nest(items),
+            Doc::Break,
+            Doc::text("]"),
+        ])
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Broken,
+}
+
+/// Lays out a [`Doc`] like Wadler's "prettier printer": a group is on one line if it fits
+/// together with what follows it up to the next possible line break.
+struct Printer {
+    width: usize,
+    output: String,
+    column: usize,
+    /// Whether nothing has been written on the current line yet, besides the indentation.
+    fresh: bool,
+}
+
+impl Printer {
+    fn new(width: usize) -> Self {
+        Printer {
+            width,
+            output: String::new(),
+            column: 0,
+            fresh: true,
+        }
+    }
+
+    fn print(mut self, doc: &Doc<'_>) -> String {
+        let mut stack = vec![(0, Mode::Broken, doc)];
+        while let Some((indent, mode, doc)) = stack.pop() {
+            match doc {
+                Doc::Text(text) => self.write(text),
+                Doc::Line | Doc::Break if mode == Mode::Broken => self.newline(indent),
+                Doc::Line => self.write(" "),
+                Doc::Break => {}
+                Doc::Nest(docs) => {
+                    stack.extend(docs.iter().rev().map(|doc| (indent + INDENT, mode, doc)))
+                }
+                Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+                Doc::Group(docs) => {
+                    let remaining = self.width as isize - self.column as isize;
+                    let mode = match mode == Mode::Flat || fits(remaining, docs, &stack) {
+                        true => Mode::Flat,
+                        false => Mode::Broken,
+                    };
+                    stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc)));
+                }
+                Doc::Comment(comment) => {
+                    if comment.own_line && !self.fresh {
+                        self.newline(indent);
+                    }
+                    if !self.fresh {
+                        self.write(" ");
+                    }
+                    self.write(comment.text);
+                    self.newline(indent);
+                }
+            }
+        }
+        self.output
+    }
+
+    fn write(&mut self, text: &str) {
+        self.output.push_str(text);
+        self.column += text.chars().count();
+        self.fresh = false;
+    }
+
+    /// Starts a new line, or changes the indentation of a new line that is still empty.
+    fn newline(&mut self, indent: usize) {
+        let trimmed = self.output.trim_end_matches(' ').len();
+        self.output.truncate(trimmed);
+        if !self.fresh {
+            self.output.push('\n');
+        }
+        self.output.extend(iter::repeat_n(' ', indent));
+        self.column = indent;
+        self.fresh = true;
+    }
+}
+
+/// Whether the group of `docs` fits on one line in `remaining` columns, together with
+/// what follows on the `stack` up to its next line break.
+fn fits(mut remaining: isize, docs: &[Doc<'_>], stack: &[(usize, Mode, &Doc<'_>)]) -> bool {
+    let mut pending: Vec<_> = docs.iter().rev().map(|doc| (Mode::Flat, doc)).collect();
+    let mut rest = stack.iter().rev().map(|(_, mode, doc)| (*mode, *doc));
+    loop {
+        let Some((mode, doc)) = pending.pop().or_else(|| rest.next()) else {
+            return true;
+        };
+        match doc {
+            Doc::Text(text) => remaining -= text.chars().count() as isize,
+            Doc::Line | Doc::Break if mode == Mode::Broken => return true,
+            Doc::Line => remaining -= 1,
+            Doc::Break => {}
+            Doc::Nest(docs) | Doc::Concat(docs) | Doc::Group(docs) => {
+                pending.extend(docs.iter().rev().map(|doc| (mode, doc)))
+            }
+            Doc::Comment(_) => return mode == Mode::Broken,
+        }
+        if remaining < 0 {
+            return false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_program, PRELUDE};
+
+    fn formatted(input: &str) -> String {
+        format(input).unwrap().value
+    }
+
+    #[track_caller]
+    fn assert_round_trips(input: &str) {
+        let output = formatted(input);
+        let original = parse_program(input).unwrap().value;
+        let reparsed = parse_program(&output).unwrap().value;
+        assert!(original.syntax_eq(&reparsed), "{output}");
+        assert_eq!(output, formatted(&output));
+    }
+
+    #[test]
+    fn keeps_formatted_prelude() {
+        let options = ParseOptions::default().with_numeric_literals();
+        let options = FormatOptions::default().with_parse_options(options);
+        assert_eq!(PRELUDE, options.format(PRELUDE).unwrap().value);
+    }
+
+    #[test]
+    fn escapes_names_starting_with_digits_only_with_numeric_literals() {
+        let input = "`0` -> (λf x.x)\n`0`\n";
+        assert_eq!("0 -> (λf x.x)\n0\n", formatted(input));
+        let options = ParseOptions::default().with_numeric_literals();
+        let options = FormatOptions::default().with_parse_options(options);
+        assert_eq!(input, options.format(input).unwrap().value);
+    }
+
+    #[test]
+    fn removes_unneeded_parentheses() {
+        assert_eq!("λx y z.a b c\n", formatted("(λx.(λy.λz.((a b) c)))"));
+        assert_eq!("f (g x) (λx.x) y\n", formatted("((f (g x)) (λx.x)) y"));
+        assert_eq!(
+            "(λx.x) (let y = z in y)\n",
+            formatted("(λx.x) (let y = z in y)")
+        );
+        assert_eq!("(a where x = y) b\n", formatted("(a where x = y) b"));
+        assert_eq!(
+            "(λx.x) where y = (z where w = v)\n",
+            formatted("(λx.x) where y = (z where w = v)")
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        let inputs = [
+            "let x = λy.y in let rec f = λn.f n in f x",
+            "a where x = λy.y where y = b",
+            "(a where x = (λy.y)) where y = b",
+            "[a, λx.x, [], [b, c]] :x \"a # b\"",
+            "import \"a.lc\"\n`in` -> (λ`0`.`0`)\nK -> ((λx.x) (λx y.x))\nK `in`",
+            "(import \"a.lc\"\n)",
+            "(import \"a.lc\" x) where x = y",
+            include_str!("../tests/maybe.lc"),
+            include_str!("../tests/is_even.lc"),
+            include_str!("../tests/is_even_let.lc"),
+        ];
+        for input in inputs {
+            assert_round_trips(input);
+        }
+    }
+
+    #[test]
+    fn keeps_comments() {
+        let input = "# first\n\n\nI -> (λx.x) # identity\nf (g # g\n# h\nh) # end\n# last";
+        assert_eq!(
+            "# first\n\nI -> (λx.x) # identity\nf\n  (g # g\n    # h\n    h) # end\n# last\n",
+            formatted(input)
+        );
+        assert_round_trips(input);
+    }
+
+    #[test]
+    fn moves_comments_before_definition_parentheses_above() {
+        let input = "K -> (λx _.x)\nI # c\n # d\n -> ( # e\nλx.x)\nI";
+        assert_eq!(
+            "K -> (λx _.x)\n# c\n# d\nI -> ( # e\nλx.x)\nI\n",
+            formatted(input)
+        );
+        assert_round_trips(input);
+    }
+
+    #[test]
+    fn wraps_long_applications() {
+        let input = "f (λx.g x x) (h a b c)";
+        let options = FormatOptions::default().with_width(16);
+        assert_eq!(
+            "f\n  (λx.g x x)\n  (h a b c)\n",
+            options.format(input).unwrap().value
+        );
+        let options = FormatOptions::default().with_width(10);
+        assert_eq!(
+            "f\n  (λx.\n    g x x)\n  (h\n    a\n    b\n    c)\n",
+            options.format(input).unwrap().value
+        );
+    }
+
+    #[test]
+    fn formats_modules() {
+        assert_eq!("I -> (λx.x)\n", formatted("I->(λx . x)"));
+        assert_eq!("", formatted("  "));
+        assert_eq!("# c\n", formatted("# c\n"));
+        let errors = format("I -> (λx.x").unwrap_err();
+        assert_eq!(1, errors.0.len());
+    }
+}
@@ -12,6 +12,15 @@ pub mod syntax;
 pub use parsers::*;
 mod diagnostics;
 pub use diagnostics::*;
-mod call_by_need;
+mod loader;
+pub use loader::*;
+mod lints;
+pub use lints::*;
+mod check;
+pub use check::*;
+mod formatter;
+pub use formatter::*;
+mod prelude;
+pub use prelude::*;
 mod default;
 pub mod evaluation;
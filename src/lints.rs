@@ -0,0 +1,350 @@
+use crate::check::check;
+use crate::diagnostics::*;
+use crate::evaluation::unknown_hint;
+use crate::syntax::*;
+use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::ptr;
+
+/// Finds likely mistakes in a program, see [`LintOptions::lint`].
+pub fn lint(program: &Program<'_>) -> Diagnostics {
+    LintOptions::default().lint(program)
+}
+
+/// Changes what [`LintOptions::lint`] warns about.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LintOptions {
+    side_effects: &'static [&'static str],
+}
+
+impl LintOptions {
+    /// Symbols that perform side effects, which aren't [`Value`](crate::evaluation::Value)
+    /// hints when they are applied at the start of the formula.
+    pub fn with_side_effects(self, side_effects: &'static [&'static str]) -> Self {
+        LintOptions { side_effects }
+    }
+
+    /// Warns about definitions that aren't needed by the formula, definitions that replace
+    /// earlier ones, unused and shadowed variables, variables that are neither bound nor
+    /// defined and hints that [`Value::decode`](crate::evaluation::Value) doesn't understand.
+    ///
+    /// Variables whose name starts with `_` may be unused. Unbound variables are the errors
+    /// of [`check`] as warnings.
+    pub fn lint(self, program: &Program<'_>) -> Diagnostics {
+        let mut linter = Linter::default();
+        let definitions = linter.definitions(&program.definitions);
+        for definition in &program.definitions {
+            let name = definition.name.value;
+            linter.definition = Some(name);
+            if ptr::eq(definitions[name], definition) {
+                linter.formula(&definition.formula, &definitions);
+            } else {
+                // A replaced definition is never used, so neither is what it uses
+                let uses = mem::take(&mut linter.uses);
+                linter.formula(&definition.formula, &definitions);
+                linter.uses = uses;
+            }
+        }
+        linter.definition = None;
+        linter.formula(&program.formula, &definitions);
+        linter.unused_definitions(&definitions);
+        self.hints(&program.formula, &mut linter.warnings);
+        let unbound = check(program).0.into_iter().map(|error| Diagnostic {
+            severity: DiagnosticSeverity::Warning,
+            ..error
+        });
+        linter.warnings.extend(unbound);
+
+        let mut warnings = linter.warnings;
+        warnings.sort_by_key(|warning| warning.labels[0].location.start);
+        Diagnostics(warnings)
+    }
+
+    fn hints(self, mut formula: &Formula<'_>, warnings: &mut Vec<Diagnostic>) {
+        while let Formula::Let(binding) = formula {
+            formula = &binding.body;
+        }
+        let mut spine = Vec::new();
+        while let Formula::App(app) = formula {
+            spine.push(&app.right);
+            formula = &app.left;
+        }
+        spine.push(formula);
+        let symbols: Vec<_> = spine
+            .iter()
+            .rev()
+            .map_while(|formula| match formula {
+                Formula::Sym(symbol) => Some(symbol),
+                _ => None,
+            })
+            .collect();
+        let hints: Vec<_> = symbols.iter().map(|symbol| symbol.ident.value).collect();
+        if symbols.is_empty()
+            || symbols.len() == spine.len()
+            || self.side_effects.contains(&hints[0])
+        {
+            return;
+        }
+        let Some(index) = unknown_hint(&hints) else {
+            return;
+        };
+        let warning = match symbols.get(index) {
+            Some(symbol) => warning(format!("unknown hint `{symbol}`"), symbol.span),
+            None => warning(
+                format!(
+                    "expected the hint of the items after `{}`",
+                    symbols[index - 1]
+                ),
+                symbols[index - 1].span,
+            ),
+        };
+        warnings.push(warning.with_help(
+            "the hints are `:n` for numbers, `:b` for booleans, `:s` for strings \
+             and `:l` followed by the hint of the items for lists",
+        ));
+    }
+}
+
+#[derive(Default)]
+struct Linter<'a> {
+    warnings: Vec<Diagnostic>,
+    /// The variables that are bound where the linter is, the innermost last.
+    scope: Vec<Bound<'a>>,
+    /// The definitions that are used by the definition the linter is in, or by the formula.
+    uses: HashMap<Option<&'a str>, HashSet<&'a str>>,
+    definition: Option<&'a str>,
+}
+
+struct Bound<'a> {
+    name: Identifier<'a>,
+    kind: &'static str,
+    used: bool,
+}
+
+impl<'a> Linter<'a> {
+    /// The definitions that are in effect, the last one of every name.
+    fn definitions<'p>(
+        &mut self,
+        definitions: &'p [NominalDefinition<'a>],
+    ) -> HashMap<&'a str, &'p NominalDefinition<'a>> {
+        let mut effective = HashMap::new();
+        for definition in definitions {
+            let name = definition.name.value;
+            if let Some(earlier) = effective.insert(name, definition) {
+                self.warnings.push(
+                    warning(format!("`{name}` is defined again"), definition.name.span).with_label(
+                        Label::new(earlier.name.span).with_message("first defined here"),
+                    ),
+                );
+            }
+        }
+        effective
+    }
+
+    fn formula(
+        &mut self,
+        formula: &Formula<'a>,
+        definitions: &HashMap<&'a str, &NominalDefinition<'a>>,
+    ) {
+        match formula {
+            Formula::Abs(abs) => {
+                self.bind(abs.variable.clone(), "parameter");
+                self.formula(&abs.formula, definitions);
+                self.unbind();
+            }
+            Formula::App(app) => {
+                self.formula(&app.left, definitions);
+                self.formula(&app.right, definitions);
+            }
+            Formula::Var(variable) => self.variable(variable, definitions),
+            Formula::List(list) => {
+                for item in &list.items {
+                    self.formula(item, definitions);
+                }
+            }
+            Formula::Let(binding) => {
+                if binding.recursive {
+                    self.bind(binding.name.clone(), "binding");
+                    self.formula(&binding.value, definitions);
+                } else {
+                    self.formula(&binding.value, definitions);
+                    self.bind(binding.name.clone(), "binding");
+                }
+                self.formula(&binding.body, definitions);
+                self.unbind();
+            }
+            Formula::Sym(_) | Formula::Num(_) | Formula::Str(_) | Formula::Error(_) => {}
+        }
+    }
+
+    fn bind(&mut self, name: Identifier<'a>, kind: &'static str) {
+        if let Some(outer) = self.scope.iter().rev().find(|b| b.name.value == name.value) {
+            self.warnings.push(
+                warning(
+                    format!("`{}` shadows an outer {}", name.value, outer.kind),
+                    name.span,
+                )
+                .with_label(Label::new(outer.name.span).with_message("shadowed variable")),
+            );
+        }
+        self.scope.push(Bound {
+            name,
+            kind,
+            used: false,
+        });
+    }
+
+    fn unbind(&mut self) {
+        let bound = self.scope.pop().unwrap_or_else(|| unreachable!());
+        if !bound.used && !bound.name.value.starts_with('_') {
+            self.warnings.push(
+                warning(
+                    format!("unused {} `{}`", bound.kind, bound.name.value),
+                    bound.name.span,
+                )
+                .with_help(format!(
+                    "if this is intentional, call it `_{}`",
+                    bound.name.value
+                )),
+            );
+        }
+    }
+
+    fn variable(
+        &mut self,
+        variable: &Identifier<'a>,
+        definitions: &HashMap<&'a str, &NominalDefinition<'a>>,
+    ) {
+        let name = variable.value;
+        if let Some(bound) = self.scope.iter_mut().rev().find(|b| b.name.value == name) {
+            bound.used = true;
+        } else if definitions.contains_key(name) {
+            self.uses.entry(self.definition).or_default().insert(name);
+        }
+    }
+
+    /// Warns about the definitions that the formula doesn't use, even indirectly.
+    fn unused_definitions(&mut self, definitions: &HashMap<&'a str, &NominalDefinition<'a>>) {
+        let mut used = HashSet::new();
+        let mut pending: Vec<_> = self.uses.get(&None).into_iter().flatten().collect();
+        while let Some(name) = pending.pop() {
+            if used.insert(*name) {
+                pending.extend(self.uses.get(&Some(*name)).into_iter().flatten());
+            }
+        }
+        for (name, definition) in definitions {
+            if !used.contains(name) && !name.starts_with('_') {
+                self.warnings.push(warning(
+                    format!("unused definition `{name}`"),
+                    definition.name.span,
+                ));
+            }
+        }
+    }
+}
+
+fn warning(message: String, location: Span) -> Diagnostic {
+    Diagnostic::new(DiagnosticSeverity::Warning, message).with_label(Label::new(location))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_program;
+
+    fn messages(input: &str) -> Vec<String> {
+        let program = parse_program(input).unwrap().value;
+        lint(&program)
+            .0
+            .into_iter()
+            .map(|warning| warning.message.into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn accepts_program_without_mistakes() {
+        let input = "I -> (λx.x)\nK -> (λx _.x)\nK I (let y = I in y) :n";
+        assert_eq!(Vec::<String>::new(), messages(input));
+    }
+
+    #[test]
+    fn warns_about_unused_variables() {
+        assert_eq!(
+            vec!["unused parameter `y`", "unused binding `z`"],
+            messages("λx y.x (let z = x in x)")
+        );
+    }
+
+    #[test]
+    fn warns_about_shadowed_variables() {
+        assert_eq!(
+            vec!["`x` shadows an outer parameter"],
+            messages("λx.x (λx.x)")
+        );
+        assert_eq!(
+            vec!["`f` shadows an outer binding"],
+            messages("let rec f = λf.f in f")
+        );
+    }
+
+    #[test]
+    fn warns_about_definitions() {
+        let input = "I -> (λx.x)\nA -> (B)\nB -> (A)\nI -> (λy.y)\nI";
+        assert_eq!(
+            vec![
+                "unused definition `A`",
+                "unused definition `B`",
+                "`I` is defined again"
+            ],
+            messages(input)
+        );
+        assert!(messages("_A -> (λx.x)\nλx.x").is_empty());
+    }
+
+    #[test]
+    fn lints_replaced_definitions() {
+        let input = "J -> (λx.x)\nI -> (λx.J y)\nI -> (λx.x)\nI";
+        assert_eq!(
+            vec![
+                "unused definition `J`",
+                "unused parameter `x`",
+                "unbound variable `y`",
+                "`I` is defined again"
+            ],
+            messages(input)
+        );
+    }
+
+    #[test]
+    fn uses_of_definitions_are_transitive() {
+        assert!(messages("I -> (λx.x)\nJ -> (I)\nK -> (J)\nK").is_empty());
+    }
+
+    #[test]
+    fn warns_about_unbound_variables() {
+        assert_eq!(
+            vec!["unbound variable `y`", "unbound variable `z`"],
+            messages("A -> (λ_x.y)\nA z")
+        );
+        let program = parse_program("incr -> (λn.n)\nincr incrr").unwrap().value;
+        let warnings = lint(&program).0;
+        assert_eq!(vec!["help: did you mean `incr`?"], warnings[0].notes);
+    }
+
+    #[test]
+    fn warns_about_unknown_hints() {
+        assert_eq!(vec!["unknown hint `:x`"], messages(":x (λx.x)"));
+        assert_eq!(vec!["unknown hint `:x`"], messages(":l :x (λx.x)"));
+        assert_eq!(
+            vec!["expected the hint of the items after `:l`"],
+            messages(":l (λx.x)")
+        );
+        assert!(messages(":l :l :b (λx.x)").is_empty());
+        assert!(messages(":x").is_empty());
+
+        let program = parse_program(":write (λx.x)").unwrap().value;
+        assert_eq!(1, lint(&program).0.len());
+        let options = LintOptions::default().with_side_effects(&["write"]);
+        assert!(options.lint(&program).0.is_empty());
+    }
+}
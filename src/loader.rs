@@ -0,0 +1,468 @@
+use crate::check::*;
+use crate::diagnostics::*;
+use crate::lints::*;
+use crate::parsers::*;
+use crate::prelude::*;
+use crate::syntax::*;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The files of a program that is spread across files with `import "path.lc"`.
+///
+/// Every file gets its own range of positions, so a [`Span`] in a diagnostic or a term
+/// tells which file it's in (see [`Sources::locate`]). The first file starts at position 0.
+#[derive(Debug)]
+pub struct Sources {
+    files: Vec<Source>,
+    /// Problems with imports that are found while loading.
+    diagnostics: Diagnostics,
+    options: ParseOptions,
+    lints: LintOptions,
+    /// Whether the errors of [`check`] make [`Sources::program`] fail.
+    strict: bool,
+    /// The index of the file with the [`PRELUDE`].
+    prelude: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct Source {
+    pub name: String,
+    pub text: String,
+    /// Where the file starts in the positions shared by all files.
+    pub offset: usize,
+    path: Option<PathBuf>,
+    imports: Vec<usize>,
+}
+
+impl Sources {
+    /// Loads the program in the file at `path` together with the files it imports.
+    ///
+    /// Only a failure to read `path` itself is an error, problems with the imports are
+    /// reported by [`Sources::program`].
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        ParseOptions::default().load(path)
+    }
+
+    /// Loads a program that's not from a file, imports are resolved relative to `directory`.
+    pub fn load_text(
+        name: impl Into<String>,
+        text: impl Into<String>,
+        directory: impl AsRef<Path>,
+    ) -> Self {
+        ParseOptions::default().load_text(name, text, directory)
+    }
+
+    /// Adds the [`PRELUDE`] as a file that is imported before everything else.
+    pub fn with_prelude(mut self) -> Self {
+        let index = self.add(
+            "<prelude>".into(),
+            PRELUDE.into(),
+            None,
+            None,
+            &mut Vec::new(),
+        );
+        self.files[0].imports.insert(0, index);
+        Sources {
+            prelude: Some(index),
+            ..self
+        }
+    }
+
+    /// Changes the warnings of [`Sources::program`].
+    pub fn with_lint_options(self, lints: LintOptions) -> Self {
+        Sources { lints, ..self }
+    }
+
+    /// Makes variables that are neither bound nor defined errors of [`Sources::program`],
+    /// instead of warnings.
+    pub fn with_strict_check(self) -> Self {
+        Sources {
+            strict: true,
+            ..self
+        }
+    }
+
+    pub fn files(&self) -> &[Source] {
+        &self.files
+    }
+
+    /// Finds the file that `span` is in and where it is in that file.
+    pub fn locate(&self, span: Span) -> Option<(usize, Span)> {
+        self.files
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, file)| file.offset <= span.start)
+            .filter(|(_, file)| span.end <= file.offset + file.text.len())
+            .map(|(index, file)| {
+                let start = span.start - file.offset;
+                let end = span.end - file.offset;
+                (index, Span { start, end })
+            })
+    }
+
+    /// Parses all files and combines them into one program with the formula of the first file.
+    ///
+    /// Definitions of imported files come before those of the importing file,
+    /// so that a file can redefine what it imports. Definitions that are used before they
+    /// are defined are errors of [`check_definitions`].
+    ///
+    /// The diagnostics of a program contain the warnings of [`LintOptions::lint`] about
+    /// the first file, except about redefining the prelude. [`Sources::with_strict_check`]
+    /// makes the errors of [`check`] fail instead.
+    pub fn program(&self) -> DiagnosticsResult<Program<'_>> {
+        let mut diagnostics = self.diagnostics.clone();
+        let mut modules = Vec::new();
+        for file in &self.files[1..] {
+            match self.options.parse_module(&file.text) {
+                Ok(module) => {
+                    let mut module = module.value;
+                    module.shift(file.offset);
+                    modules.push(module.definitions);
+                }
+                Err(errors) => diagnostics.0.extend(shifted(errors, file.offset).0),
+            }
+        }
+        let mut program = match self.options.parse_program(&self.files[0].text) {
+            Ok(program) => program.value,
+            Err(errors) => {
+                diagnostics.0.extend(errors.0);
+                return Err(diagnostics);
+            }
+        };
+        if !diagnostics.0.is_empty() {
+            return Err(diagnostics);
+        }
+        let mut definitions = Vec::new();
+        for index in self.dependencies() {
+            definitions.append(&mut modules[index - 1]);
+        }
+        definitions.append(&mut program.definitions);
+        program.definitions = definitions;
+        let errors = check_definitions(&program.definitions);
+        if !errors.0.is_empty() {
+            return Err(errors);
+        }
+        if self.strict {
+            let errors = check(&program);
+            if !errors.0.is_empty() {
+                return Err(errors);
+            }
+        }
+        let mut warnings = self.lints.lint(&program);
+        warnings.0.retain(|warning| {
+            let mut locations = warning.labels.iter().map(|label| label.location);
+            self.is_in(0, warning.labels[0].location)
+                && !locations.any(|location| self.prelude.is_some_and(|p| self.is_in(p, location)))
+        });
+        Ok(WithDiagnostics {
+            value: program,
+            diagnostics: warnings,
+        })
+    }
+
+    fn is_in(&self, file: usize, span: Span) -> bool {
+        self.locate(span).is_some_and(|(index, _)| index == file)
+    }
+
+    fn add(
+        &mut self,
+        name: String,
+        text: String,
+        path: Option<PathBuf>,
+        directory: Option<&Path>,
+        stack: &mut Vec<usize>,
+    ) -> usize {
+        let offset = self
+            .files
+            .last()
+            .map_or(0, |file| file.offset + file.text.len() + 1);
+        let index = self.files.len();
+        self.files.push(Source {
+            name,
+            text,
+            offset,
+            path,
+            imports: Vec::new(),
+        });
+        if let Some(directory) = directory {
+            self.resolve_imports(index, directory, stack);
+        }
+        index
+    }
+
+    fn resolve_imports(&mut self, index: usize, directory: &Path, stack: &mut Vec<usize>) {
+        stack.push(index);
+        for (path, span) in self.imports_of(index) {
+            let path = directory.join(path);
+            match self.load_import(&path, stack) {
+                Ok(imported) => self.files[index].imports.push(imported),
+                Err(message) => self.diagnostics.0.push(
+                    Diagnostic::new(DiagnosticSeverity::Error, message)
+                        .with_label(Label::new(span)),
+                ),
+            }
+        }
+        stack.pop();
+    }
+
+    /// The paths and (shifted) spans of the imports in a file, even if it has syntax errors.
+    fn imports_of(&self, index: usize) -> Vec<(String, Span)> {
+        let file = &self.files[index];
+        let imports = match index {
+            0 => self.options.recover_program(&file.text).value.imports,
+            _ => self.options.recover_module(&file.text).value.imports,
+        };
+        imports
+            .into_iter()
+            .map(|mut import| {
+                import.shift(file.offset);
+                (import.path.to_owned(), import.span)
+            })
+            .collect()
+    }
+
+    fn load_import(&mut self, path: &Path, stack: &mut Vec<usize>) -> Result<usize, String> {
+        let name = path.to_string_lossy().into_owned();
+        let error = |error: io::Error| format!("cannot import `{name}`: {error}");
+        let canonical = fs::canonicalize(path).map_err(error)?;
+        let loaded = self
+            .files
+            .iter()
+            .position(|file| file.path.as_ref() == Some(&canonical));
+        match loaded {
+            Some(index) if stack.contains(&index) => {
+                let start = stack.iter().position(|i| *i == index).unwrap_or_default();
+                let cycle: Vec<_> = stack[start..]
+                    .iter()
+                    .chain([&index])
+                    .map(|i| self.files[*i].name.as_str())
+                    .collect();
+                Err(format!("import cycle: {}", cycle.join(" → ")))
+            }
+            Some(index) => Ok(index),
+            None => {
+                let text = fs::read_to_string(path).map_err(error)?;
+                Ok(self.add(name, text, Some(canonical), path.parent(), stack))
+            }
+        }
+    }
+
+    /// The imported files in an order where every file comes after the files it imports.
+    fn dependencies(&self) -> Vec<usize> {
+        fn visit(sources: &Sources, index: usize, order: &mut Vec<usize>) {
+            for &import in &sources.files[index].imports {
+                if !order.contains(&import) {
+                    visit(sources, import, order);
+                    order.push(import);
+                }
+            }
+        }
+        let mut order = Vec::new();
+        visit(self, 0, &mut order);
+        order
+    }
+}
+
+impl ParseOptions {
+    /// Like [`Sources::load`], but parses all files with these options.
+    ///
+    /// The options already matter for finding the imports.
+    pub fn load(self, path: impl AsRef<Path>) -> io::Result<Sources> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        let mut sources = self.sources();
+        let name = path.to_string_lossy().into_owned();
+        let canonical = fs::canonicalize(path)?;
+        sources.add(name, text, Some(canonical), path.parent(), &mut Vec::new());
+        Ok(sources)
+    }
+
+    /// Like [`Sources::load_text`], but parses all files with these options.
+    pub fn load_text(
+        self,
+        name: impl Into<String>,
+        text: impl Into<String>,
+        directory: impl AsRef<Path>,
+    ) -> Sources {
+        let mut sources = self.sources();
+        let directory = Some(directory.as_ref());
+        sources.add(name.into(), text.into(), None, directory, &mut Vec::new());
+        sources
+    }
+
+    /// Sources without files, which [`Sources::add`] has to add the first one to.
+    fn sources(self) -> Sources {
+        Sources {
+            files: Vec::new(),
+            diagnostics: Diagnostics::default(),
+            options: self,
+            lints: LintOptions::default(),
+            strict: false,
+            prelude: None,
+        }
+    }
+}
+
+fn shifted(mut diagnostics: Diagnostics, offset: usize) -> Diagnostics {
+    for label in diagnostics.0.iter_mut().flat_map(|d| d.labels.iter_mut()) {
+        label.location.shift(offset);
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation::{evaluate_program, Encode as _};
+
+    const IMPORTS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/imports");
+
+    fn load(name: &str) -> Sources {
+        Sources::load(Path::new(IMPORTS).join(name)).unwrap()
+    }
+
+    fn error(sources: &Sources) -> (String, String, Span) {
+        let diagnostics = sources.program().unwrap_err().0;
+        assert_eq!(1, diagnostics.len());
+        let diagnostic = &diagnostics[0];
+        let (file, span) = sources.locate(diagnostic.labels[0].location).unwrap();
+        (
+            diagnostic.message.to_string(),
+            sources.files()[file].name.clone(),
+            span,
+        )
+    }
+
+    #[test]
+    fn combines_imported_definitions() {
+        let sources = load("maybe.lc");
+        assert_eq!(4, sources.files().len());
+        let program = sources.program().unwrap().value;
+        let normal_form = evaluate_program(program).last().unwrap().term;
+        assert_eq!(None::<u64>.encode(), normal_form);
+    }
+
+    #[test]
+    fn imports_every_file_only_once() {
+        let sources = Sources::load_text(
+            "<test>",
+            "import \"lib/numerals.lc\"\nimport \"lib/booleans.lc\"\nisEven 3",
+            IMPORTS,
+        );
+        assert_eq!(3, sources.files().len());
+        let program = sources.program().unwrap().value;
+        let normal_form = evaluate_program(program).last().unwrap().term;
+        // `False` as defined in lib/booleans.lc
+        assert_eq!(crate::λ!(λt f.f), normal_form);
+    }
+
+    #[test]
+    fn finds_imports_with_the_parse_options() {
+        let sources = ParseOptions::default().with_numeric_literals().load_text(
+            "<test>",
+            "one -> (1)\nimport \"lib/booleans.lc\"\none not True",
+            IMPORTS,
+        );
+        assert_eq!(2, sources.files().len());
+        let program = sources.program().unwrap().value;
+        let normal_form = evaluate_program(program).last().unwrap().term;
+        assert_eq!(crate::λ!(λt f.f), normal_form);
+    }
+
+    #[test]
+    fn reports_import_cycles() {
+        let (message, file, span) = error(&load("cycle.lc"));
+        assert!(message.starts_with("import cycle: "), "{message}");
+        assert!(file.ends_with("cycle_b.lc"));
+        assert_eq!(Span { start: 0, end: 19 }, span);
+    }
+
+    #[test]
+    fn reports_missing_imports() {
+        let (message, file, span) = error(&load("missing.lc"));
+        assert!(message.starts_with("cannot import "), "{message}");
+        assert!(file.ends_with("missing.lc"));
+        assert_eq!(Span { start: 0, end: 23 }, span);
+    }
+
+    #[test]
+    fn reports_syntax_errors_in_the_imported_file() {
+        let sources = load("broken.lc");
+        let (_, file, span) = error(&sources);
+        assert!(file.ends_with("lib/broken.lc"));
+        let text = &sources.files()[1].text;
+        assert!(span.start > text.find('K').unwrap(), "{span:?}");
+    }
+
+    #[test]
+    fn warns_only_about_the_first_file() {
+        let sources =
+            Sources::load_text("<test>", "True -> (λ_a b.b)\nλx.True", ".").with_prelude();
+        let program = sources.program().unwrap();
+        let messages: Vec<_> = program.diagnostics.0.iter().map(|d| &d.message).collect();
+        assert_eq!(vec!["unused parameter `x`"], messages);
+        let redefined = program
+            .definitions
+            .iter()
+            .filter(|d| d.name.value == "True");
+        assert_eq!(2, redefined.count());
+    }
+
+    #[test]
+    fn locates_spans_in_their_file() {
+        let sources = load("maybe.lc");
+        for (index, file) in sources.files().iter().enumerate() {
+            let span = Span {
+                start: file.offset + 1,
+                end: file.offset + file.text.len(),
+            };
+            assert_eq!(
+                Some((
+                    index,
+                    Span {
+                        start: 1,
+                        end: file.text.len()
+                    }
+                )),
+                sources.locate(span)
+            );
+        }
+    }
+
+    #[test]
+    fn redefinitions_can_use_what_they_redefine() {
+        let sources =
+            Sources::load_text("<test>", "not -> (λb.not (not b))\nnot True", ".").with_prelude();
+        let program = sources.program().unwrap().value;
+        let normal_form = evaluate_program(program).last().unwrap().term;
+        assert_eq!(true.encode(), normal_form);
+    }
+
+    #[test]
+    fn reports_definitions_that_are_used_before_they_are_defined() {
+        let sources = Sources::load_text("<test>", "A -> (λx.B x)\nB -> (λx.x)\nA", ".");
+        let (message, _, span) = error(&sources);
+        assert_eq!("`B` is used before it is defined", message);
+        assert_eq!(Span { start: 10, end: 11 }, span);
+
+        let sources = Sources::load_text("<test>", "f -> (λn.f n)\nf", ".");
+        let (message, _, _) = error(&sources);
+        assert_eq!("`f` is used before it is defined", message);
+    }
+
+    #[test]
+    fn unbound_variables_fail_only_strict_check() {
+        let sources = Sources::load_text("<test>", "λx.x Tru", ".").with_prelude();
+        let program = sources.program().unwrap();
+        let diagnostic = &program.diagnostics.0[0];
+        assert_eq!(DiagnosticSeverity::Warning, diagnostic.severity);
+        assert_eq!("unbound variable `Tru`", diagnostic.message);
+        assert_eq!(vec!["help: did you mean `True`?"], diagnostic.notes);
+
+        let errors = sources.with_strict_check().program().unwrap_err();
+        assert_eq!(1, errors.0.len());
+        assert_eq!(DiagnosticSeverity::Error, errors.0[0].severity);
+    }
+}
@@ -1,17 +1,22 @@
 use crate::diagnostics::*;
+use crate::evaluation::ListEncoding;
 use crate::syntax::*;
+use std::cell::RefCell;
+use std::rc::Rc;
 use trait_set::trait_set;
 use unicode_xid::UnicodeXID;
-use winnow::ascii::{multispace1, not_line_ending};
-use winnow::combinator::{alt, cut_err, fold_repeat, repeat};
-use winnow::error::{VerboseError, VerboseErrorKind};
-use winnow::sequence::{delimited, preceded};
-use winnow::stream::Location;
-use winnow::token::{one_of, take_while};
+use winnow::ascii::{digit1, multispace1, not_line_ending};
+use winnow::combinator::{alt, cut_err, fail, fold_repeat, not, opt, peek, repeat, separated0};
+use winnow::error::{ErrMode, VerboseError};
+use winnow::sequence::{delimited, preceded, terminated};
+use winnow::token::{none_of, one_of, take_till0, take_while};
 use winnow::trace::trace;
-use winnow::{Located, Parser as _};
+use winnow::{Located, Parser as _, Stateful};
 
-type Input<'a> = Located<&'a str>;
+mod errors;
+use errors::*;
+
+type Input<'a> = Stateful<Located<&'a str>, State<'a>>;
 type Error<'a> = VerboseError<Input<'a>>;
 type IResult<'a, O> = winnow::IResult<Input<'a>, O, Error<'a>>;
 
@@ -20,61 +25,361 @@ trait_set! {
 }
 
 pub fn parse_program(input: &str) -> DiagnosticsResult<Program<'_>> {
-    program
-        .parse(Input::new(input))
-        .map(WithDiagnostics::with_empty_diagnostics)
-        .map_err(to_diagnostics)
+    ParseOptions::default().parse_program(input)
+}
+
+/// Parses a file that only contains imports and definitions.
+pub fn parse_module(input: &str) -> DiagnosticsResult<Module<'_>> {
+    ParseOptions::default().parse_module(input)
 }
 
 pub fn parse_formula(input: &str) -> DiagnosticsResult<Formula<'_>> {
-    formula
-        .parse(Input::new(input))
-        .map(WithDiagnostics::with_empty_diagnostics)
-        .map_err(to_diagnostics)
-}
-
-fn to_diagnostics(error: Error) -> Diagnostics {
-    let (input, error) = error
-        .errors
-        .first()
-        .expect("At least one error was expected");
-    let location = input.location();
-    let span = Span {
-        start: location,
-        end: location,
-    };
-    Diagnostics(vec![Diagnostic::new(
-        DiagnosticSeverity::Error,
-        error_to_message(error),
-    )
-    .with_label(Label::new(span))])
+    ParseOptions::default().parse_formula(input)
+}
+
+/// Changes how the input is parsed, e.g. [`ParseOptions::with_numeric_literals`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    numeric_literals: bool,
+    list_encoding: ListEncoding,
 }
 
-fn error_to_message(error: &VerboseErrorKind) -> &'static str {
-    match error {
-        VerboseErrorKind::Context(context) => context,
-        VerboseErrorKind::Winnow(_) => "unexpected input",
+impl ParseOptions {
+    /// Parses integers like `42` as Church numerals (see [`Numeral`]).
+    /// Variables whose names start with a digit have to be written as `` `42` `` then.
+    /// Literals larger than [`Numeral::MAX`] are errors.
+    pub fn with_numeric_literals(self) -> Self {
+        ParseOptions {
+            numeric_literals: true,
+            ..self
+        }
+    }
+
+    pub(crate) fn numeric_literals(self) -> bool {
+        self.numeric_literals
+    }
+
+    /// How list and string literals are encoded, Church lists by default.
+    pub fn with_list_encoding(self, list_encoding: ListEncoding) -> Self {
+        ParseOptions {
+            list_encoding,
+            ..self
+        }
+    }
+
+    /// Parses a program, the error contains every syntax error that was found.
+    pub fn parse_program(self, input: &str) -> DiagnosticsResult<Program<'_>> {
+        failed_on_errors(self.recover_program(input))
+    }
+
+    /// Parses a file that only contains imports and definitions.
+    pub fn parse_module(self, input: &str) -> DiagnosticsResult<Module<'_>> {
+        failed_on_errors(self.recover_module(input))
+    }
+
+    pub fn parse_formula(self, input: &str) -> DiagnosticsResult<Formula<'_>> {
+        let input = self.input(input);
+        let errors = Rc::clone(&input.state.errors);
+        let result = formula.parse(input);
+        let mut diagnostics = errors.take();
+        match result {
+            Ok(formula) if diagnostics.0.is_empty() => {
+                Ok(WithDiagnostics::with_empty_diagnostics(formula))
+            }
+            Ok(_) => Err(diagnostics),
+            Err(error) => {
+                diagnostics.0.push(to_diagnostic(error));
+                Err(diagnostics)
+            }
+        }
+    }
+
+    /// Parses a program even if it has syntax errors. Parsing continues after an error
+    /// at the next closing parenthesis or definition, the skipped input becomes a
+    /// [`Formula::Error`]. The diagnostics contain every error.
+    pub fn recover_program(self, input: &str) -> WithDiagnostics<Program<'_>> {
+        self.recover(program, empty_program, input)
+    }
+
+    /// Like [`ParseOptions::recover_program`] for a file with only imports and definitions.
+    pub fn recover_module(self, input: &str) -> WithDiagnostics<Module<'_>> {
+        self.recover(module, empty_module, input)
+    }
+
+    /// Like [`ParseOptions::parse_program`], together with the spans of the comments in order.
+    pub(crate) fn parse_program_with_comments(
+        self,
+        input: &str,
+    ) -> DiagnosticsResult<(Program<'_>, Vec<Span>)> {
+        let (program, comments) = self.recover_with_comments(program, empty_program, input);
+        failed_on_errors(program).map(|program| WithDiagnostics {
+            value: (program.value, comments),
+            diagnostics: program.diagnostics,
+        })
+    }
+
+    /// Like [`ParseOptions::parse_module`], together with the spans of the comments in order.
+    pub(crate) fn parse_module_with_comments(
+        self,
+        input: &str,
+    ) -> DiagnosticsResult<(Module<'_>, Vec<Span>)> {
+        let (module, comments) = self.recover_with_comments(module, empty_module, input);
+        failed_on_errors(module).map(|module| WithDiagnostics {
+            value: (module.value, comments),
+            diagnostics: module.diagnostics,
+        })
+    }
+
+    fn recover<'a, O>(
+        self,
+        parser: impl Parser<'a, O>,
+        empty: fn(Span) -> O,
+        input: &'a str,
+    ) -> WithDiagnostics<O> {
+        self.recover_with_comments(parser, empty, input).0
+    }
+
+    /// Runs a recovering `parser`, which only fails if it's wrong. The error is reported
+    /// then and the result is what `empty` makes of the span of the input.
+    fn recover_with_comments<'a, O>(
+        self,
+        mut parser: impl Parser<'a, O>,
+        empty: fn(Span) -> O,
+        text: &'a str,
+    ) -> (WithDiagnostics<O>, Vec<Span>) {
+        let input = self.input(text);
+        let errors = Rc::clone(&input.state.errors);
+        let comments = Rc::clone(&input.state.comments);
+        let value = match parser.parse(input) {
+            Ok(value) => value,
+            Err(error) => {
+                errors.borrow_mut().0.push(to_diagnostic(error));
+                empty((0..text.len()).into())
+            }
+        };
+        let mut comments = comments.take();
+        // Comments are parsed again when parsers backtrack
+        comments.sort_by_key(|comment| comment.start);
+        comments.dedup();
+        let value = WithDiagnostics {
+            value,
+            diagnostics: errors.take(),
+        };
+        (value, comments)
+    }
+
+    fn input(self, input: &str) -> Input<'_> {
+        Stateful {
+            input: Located::new(input),
+            state: State {
+                options: self,
+                errors: Rc::default(),
+                comments: Rc::default(),
+                text: input,
+            },
+        }
+    }
+}
+
+/// What parsers share besides the input: the options and the errors that were recovered from.
+#[derive(Debug, Clone)]
+struct State<'a> {
+    options: ParseOptions,
+    errors: Rc<RefCell<Diagnostics>>,
+    /// The comments that were parsed, for the formatter to keep them.
+    comments: Rc<RefCell<Vec<Span>>>,
+    /// All of the input, to look behind an error.
+    text: &'a str,
+}
+
+impl State<'_> {
+    fn record(&self, diagnostic: Diagnostic) {
+        self.errors.borrow_mut().0.push(diagnostic);
+    }
+}
+
+fn failed_on_errors<T>(result: WithDiagnostics<T>) -> DiagnosticsResult<T> {
+    match result.diagnostics.0.is_empty() {
+        true => Ok(result),
+        false => Err(result.diagnostics),
     }
 }
 
 fn program(input: Input<'_>) -> IResult<'_, Program<'_>> {
-    (
-        repeat(.., delimited(trivia, nominal_definition, trivia)),
-        formula,
-    )
+    items(formula.map(Item::Formula))
         .with_span()
-        .map(|((definitions, formula), span)| Program {
-            definitions,
-            formula,
+        .map(|(items, span)| {
+            check_formula_is_last(&items, &input.state);
+            let (imports, definitions, formulas) = partitioned(items);
+            let span: Span = span.into();
+            // A missing formula may have been skipped after an error
+            let formula = formulas.into_iter().last().unwrap_or_else(|| {
+                let end = Span {
+                    start: span.end,
+                    end: span.end,
+                };
+                if input.state.errors.borrow().0.is_empty() {
+                    input.state.record(
+                        Diagnostic::new(DiagnosticSeverity::Error, EXPECTED_FORMULA)
+                            .with_label(Label::new(end)),
+                    );
+                }
+                Formula::Error(end)
+            });
+            Program {
+                imports,
+                definitions,
+                formula,
+                span,
+            }
+        })
+        .parse_next(input.clone())
+}
+
+fn empty_program(span: Span) -> Program<'static> {
+    Program {
+        imports: Vec::new(),
+        definitions: Vec::new(),
+        formula: Formula::Error(span),
+        span,
+    }
+}
+
+fn module(input: Input<'_>) -> IResult<'_, Module<'_>> {
+    items(fail)
+        .with_span()
+        .map(|(items, span)| {
+            let (imports, definitions, _) = partitioned(items);
+            Module {
+                imports,
+                definitions,
+                span: span.into(),
+            }
+        })
+        .parse_next(input)
+}
+
+fn empty_module(span: Span) -> Module<'static> {
+    Module {
+        imports: Vec::new(),
+        definitions: Vec::new(),
+        span,
+    }
+}
+
+enum Item<'a> {
+    Import(Import<'a>),
+    Definition(NominalDefinition<'a>),
+    Formula(Formula<'a>),
+    Skipped,
+}
+
+/// Imports, definitions and what `other` parses, in any order. Anything else is reported
+/// and skipped up to the next definition. The trivia at the end is part of the items,
+/// so that the items of input without any still end at the end of the input.
+fn items<'a>(other: impl Parser<'a, Item<'a>>) -> impl Parser<'a, Vec<Item<'a>>> {
+    let mut item = alt((
+        import.map(Item::Import),
+        nominal_definition.map(Item::Definition),
+        other,
+    ));
+    let item = move |input: Input<'a>| match item.parse_next(input.clone()) {
+        result @ Err(ErrMode::Backtrack(_)) if input.is_empty() => result,
+        Err(ErrMode::Backtrack(error) | ErrMode::Cut(error)) => {
+            input.state.record(to_diagnostic(error));
+            repeat(1.., alt((skipped_part, discarded(')'))))
+                .map(|()| Item::Skipped)
+                .parse_next(input)
+        }
+        result => result,
+    };
+    terminated(repeat(.., delimited(trivia, item, trivia)), trivia)
+}
+
+fn check_formula_is_last(items: &[Item], state: &State) {
+    let mut formula = None;
+    for item in items {
+        match item {
+            Item::Formula(Formula::Error(_)) | Item::Skipped => {}
+            // Likely part of the formula, whose error was reported already
+            Item::Definition(NominalDefinition {
+                formula: Formula::Error(_),
+                ..
+            }) => {}
+            Item::Formula(last) => formula = Some(*last.span()),
+            Item::Import(Import { span, .. })
+            | Item::Definition(NominalDefinition { span, .. }) => {
+                if let Some(formula) = formula.take() {
+                    state.record(formula_before_item(formula, *span));
+                }
+            }
+        }
+    }
+}
+
+fn partitioned<'a>(
+    items: Vec<Item<'a>>,
+) -> (
+    Vec<Import<'a>>,
+    Vec<NominalDefinition<'a>>,
+    Vec<Formula<'a>>,
+) {
+    let (mut imports, mut definitions, mut formulas) = (Vec::new(), Vec::new(), Vec::new());
+    for item in items {
+        match item {
+            Item::Import(import) => imports.push(import),
+            Item::Definition(definition) => definitions.push(definition),
+            Item::Formula(formula) => formulas.push(formula),
+            Item::Skipped => {}
+        }
+    }
+    (imports, definitions, formulas)
+}
+
+fn import(input: Input) -> IResult<Import> {
+    preceded(("import", trivia), string)
+        .with_span()
+        .map(|(path, span)| Import {
+            path,
             span: span.into(),
         })
         .parse_next(input)
 }
 
+fn string(input: Input<'_>) -> IResult<'_, &str> {
+    preceded(
+        '"',
+        cut_err(terminated(take_till0('"'), '"'.context(EXPECTED_QUOTE))),
+    )
+    .parse_next(input)
+}
+
+/// An application, optionally followed by `where` bindings.
 fn formula(input: Input<'_>) -> IResult<'_, Formula<'_>> {
+    let clause = preceded(keyword("where"), cut_err(binding(required(application)))).with_span();
+    (application, repeat(.., clause))
+        .map(|(body, clauses): (_, Vec<_>)| {
+            clauses
+                .into_iter()
+                .fold(body, |body, ((recursive, name, value), span)| {
+                    let span = Span::containing(body.span(), &span.into());
+                    Formula::Let(Box::new(Let {
+                        style: LetStyle::Where,
+                        recursive,
+                        name,
+                        value,
+                        body,
+                        span,
+                    }))
+                })
+        })
+        .parse_next(input)
+}
+
+fn application(input: Input<'_>) -> IResult<'_, Formula<'_>> {
     fold_repeat(
         1..,
-        delimited(trivia, one_formula, trivia),
+        delimited(trivia, preceded(not(definition_start), one_formula), trivia),
         || None,
         apply_formula,
     )
@@ -94,19 +399,63 @@ fn apply_formula<'a>(left: Option<Formula<'a>>, right: Formula<'a>) -> Option<Fo
 
 fn one_formula(input: Input) -> IResult<Formula> {
     alt((
-        trace("parenthesized", parenthesized(formula)),
+        trace("parenthesized", parenthesized),
         trace("abstraction", abstraction).map(Formula::abs),
+        trace("let", let_binding).map(|binding| Formula::Let(Box::new(binding))),
         trace("symbol", symbol).map(Formula::Sym),
+        trace("numeral", numeral),
+        trace("list", list).map(Formula::List),
+        trace("text", text).map(Formula::Str),
         trace("identifier", identifier).map(Formula::Var),
     ))
     .parse_next(input)
 }
 
 fn abstraction(input: Input) -> IResult<Abstraction> {
-    preceded(lambda, cut_err((variable_list, preceded('.', formula))))
-        .with_span()
-        .map(|((variables, formula), span)| create_abstraction(variables, formula, span.into()))
-        .parse_next(input)
+    preceded(
+        lambda,
+        cut_err((
+            variable_list,
+            preceded('.'.context(EXPECTED_DOT), required(formula)),
+        )),
+    )
+    .with_span()
+    .map(|((variables, formula), span)| create_abstraction(variables, formula, span.into()))
+    .parse_next(input)
+}
+
+fn let_binding(input: Input) -> IResult<Let> {
+    preceded(
+        keyword("let"),
+        cut_err((
+            binding(required(formula)),
+            preceded(keyword("in").context(EXPECTED_IN), required(formula)),
+        )),
+    )
+    .with_span()
+    .map(|(((recursive, name, value), body), span)| Let {
+        style: LetStyle::Let,
+        recursive,
+        name,
+        value,
+        body,
+        span: span.into(),
+    })
+    .parse_next(input)
+}
+
+/// `x = value` or `rec x = value` of a [`Let`].
+fn binding<'a>(
+    value: impl Parser<'a, Formula<'a>>,
+) -> impl Parser<'a, (bool, Identifier<'a>, Formula<'a>)> {
+    (
+        preceded(trivia, opt(terminated(keyword("rec"), trivia))).map(|rec| rec.is_some()),
+        terminated(
+            identifier.context(EXPECTED_BOUND_NAME),
+            (trivia, '='.context(EXPECTED_EQUALS)),
+        ),
+        value,
+    )
 }
 
 fn create_abstraction<'a>(
@@ -118,21 +467,26 @@ fn create_abstraction<'a>(
     let innermost = Abstraction {
         variable: variables.next_back().unwrap_or_else(|| unreachable!()),
         formula,
-        span: span.clone(),
+        span,
     };
     variables.rfold(innermost, |abs, variable| Abstraction {
         variable,
         formula: Formula::abs(abs),
-        span: span.clone(),
+        span,
     })
 }
 
+/// The start of a definition, where a formula ends and where parsing resumes after an error.
+fn definition_start(input: Input) -> IResult<()> {
+    discarded((identifier, trivia, "->")).parse_next(input)
+}
+
 fn nominal_definition(input: Input) -> IResult<NominalDefinition> {
     (
         identifier,
         preceded(
-            (trivia, "->"),
-            cut_err(preceded(trivia, parenthesized(formula))),
+            (trivia, "->", trivia),
+            recovering(parenthesized.context(EXPECTED_DEFINITION_FORMULA)),
         ),
     )
         .with_span()
@@ -145,7 +499,11 @@ fn nominal_definition(input: Input) -> IResult<NominalDefinition> {
 }
 
 fn variable_list(input: Input) -> IResult<Vec<Identifier>> {
-    repeat(1.., delimited(trivia, identifier, trivia)).parse_next(input)
+    repeat(
+        1..,
+        delimited(trivia, identifier.context(EXPECTED_PARAMETER), trivia),
+    )
+    .parse_next(input)
 }
 
 fn lambda(input: Input) -> IResult<char> {
@@ -153,7 +511,7 @@ fn lambda(input: Input) -> IResult<char> {
 }
 
 fn symbol(input: Input) -> IResult<Symbol> {
-    preceded(':', cut_err(identifier))
+    preceded(':', cut_err(identifier.context(EXPECTED_SYMBOL)))
         .with_span()
         .map(|(ident, span)| Symbol {
             ident,
@@ -162,7 +520,84 @@ fn symbol(input: Input) -> IResult<Symbol> {
         .parse_next(input)
 }
 
+fn list(input: Input) -> IResult<List> {
+    let encoding = input.state.options.list_encoding;
+    preceded(
+        '[',
+        cut_err(terminated(
+            separated0(formula, ','),
+            (trivia, ']'.context(EXPECTED_LIST_END)),
+        )),
+    )
+    .with_span()
+    .map(|(items, span)| List {
+        items,
+        encoding,
+        span: span.into(),
+    })
+    .parse_next(input)
+}
+
+/// A string literal, which is an error if it has a character above [`Numeral::MAX`].
+fn text(input: Input) -> IResult<Text> {
+    let encoding = input.state.options.list_encoding;
+    let contents = take_while(0.., |c| c != '"' && u64::from(c) <= Numeral::MAX);
+    let end = (
+        not(none_of('"')).context(CHARACTER_TOO_LARGE),
+        '"'.context(EXPECTED_QUOTE),
+    );
+    preceded('"', cut_err(terminated(contents, end)))
+        .with_span()
+        .map(|(value, span)| Text {
+            value,
+            encoding,
+            span: span.into(),
+        })
+        .parse_next(input)
+}
+
+/// A numeric literal, which is an error above [`Numeral::MAX`].
+fn numeral(input: Input) -> IResult<Formula> {
+    let numeric_literals = input.state.options.numeric_literals;
+    let digits = terminated(digit1, not(one_of(is_identifier_continue)))
+        .verify(move |_: &str| numeric_literals);
+    let value = digit1
+        .try_map(str::parse)
+        .verify(|value: &u64| *value <= Numeral::MAX)
+        .context(NUMERAL_TOO_LARGE);
+    preceded(peek(digits), cut_err(value))
+        .with_span()
+        .map(|(value, span)| {
+            Formula::Num(Numeral {
+                value,
+                span: span.into(),
+            })
+        })
+        .parse_next(input)
+}
+
+/// A name that isn't a keyword, or with numeric literals a name that doesn't start with a digit.
+/// Any name can be escaped as in `` `42` `` or `` `let` ``.
 fn identifier(input: Input) -> IResult<Identifier> {
+    let numeric_literals = input.state.options.numeric_literals;
+    alt((
+        preceded(
+            '`',
+            cut_err(terminated(name, '`'.context(EXPECTED_BACKTICK))),
+        ),
+        name.verify(move |name: &Identifier| {
+            !(numeric_literals && name.value.starts_with(|c: char| c.is_ascii_digit()))
+                && !KEYWORDS.contains(&name.value)
+        }),
+    ))
+    .parse_next(input)
+}
+
+fn keyword<'a>(keyword: &'static str) -> impl Parser<'a, &'a str> {
+    terminated(keyword, not(one_of(is_identifier_continue)))
+}
+
+fn name(input: Input) -> IResult<Identifier> {
     (
         one_of(is_identifier_start),
         take_while(0.., is_identifier_continue),
@@ -191,11 +626,68 @@ fn trivia(input: Input) -> IResult<()> {
 }
 
 fn comment(input: Input) -> IResult<()> {
-    discarded(('#', not_line_ending)).parse_next(input)
+    let (rest, span) = ('#', not_line_ending).span().parse_next(input)?;
+    rest.state.comments.borrow_mut().push(span.into());
+    Ok((rest, ()))
+}
+
+fn parenthesized(input: Input) -> IResult<Formula> {
+    let (input, opening) = '('.span().parse_next(input)?;
+    let opening = opening.into();
+    let inner = delimited(
+        trivia,
+        required(formula),
+        (trivia, ')'.context(EXPECTED_CLOSING_PARENTHESIS)),
+    );
+    recovering_with(inner, move |diagnostic, closed| {
+        with_opening_parenthesis(diagnostic, opening, closed)
+    })
+    .parse_next(input)
+}
+
+/// A formula that has to come next, e.g. the body of an abstraction.
+fn required<'a>(parser: impl Parser<'a, Formula<'a>>) -> impl Parser<'a, Formula<'a>> {
+    preceded(trivia, parser.context(EXPECTED_FORMULA))
+}
+
+/// Records the error if `parser` fails and skips the input up to the closing parenthesis
+/// or the next definition instead, see [`skipped`].
+fn recovering<'a>(parser: impl Parser<'a, Formula<'a>>) -> impl Parser<'a, Formula<'a>> {
+    recovering_with(parser, |diagnostic, _| diagnostic)
 }
 
-fn parenthesized<'a, O>(parser: impl Parser<'a, O>) -> impl Parser<'a, O> {
-    preceded('(', cut_err(delimited(trivia, parser, (trivia, ')'))))
+/// Like [`recovering`], `adjust` gets the error and whether a closing parenthesis was skipped.
+fn recovering_with<'a>(
+    mut parser: impl Parser<'a, Formula<'a>>,
+    adjust: impl Fn(Diagnostic, bool) -> Diagnostic,
+) -> impl Parser<'a, Formula<'a>> {
+    move |input: Input<'a>| match parser.parse_next(input.clone()) {
+        Err(ErrMode::Backtrack(error) | ErrMode::Cut(error)) => {
+            let (rest, ((_, closing), span)) =
+                (skipped, opt(')')).with_span().parse_next(input.clone())?;
+            let closed = closing.is_some();
+            input.state.record(adjust(to_diagnostic(error), closed));
+            Ok((rest, Formula::Error(span.into())))
+        }
+        result => result,
+    }
+}
+
+/// Input up to an unmatched `)` or a line that starts with a definition.
+fn skipped(input: Input) -> IResult<()> {
+    repeat(.., skipped_part).parse_next(input)
+}
+
+fn skipped_part(input: Input) -> IResult<()> {
+    alt((
+        comment,
+        // An unclosed string shouldn't hide the definitions after it
+        discarded(('"', take_till0(['"', '\n']), opt('"'))),
+        discarded(('(', skipped, opt(')'))),
+        discarded(terminated('\n', not(peek((trivia, definition_start))))),
+        discarded(none_of("()\"#\n")),
+    ))
+    .parse_next(input)
 }
 
 fn discarded<'a, O>(parser: impl Parser<'a, O>) -> impl Parser<'a, ()> {
@@ -223,7 +715,9 @@ mod tests {
     #[test]
     fn does_not_parse_invalid_identifers() {
         for text in NON_IDENTIFIERS {
-            assert!(matches!(identifier.parse(Input::new(text)), Err(_)))
+            assert!(identifier
+                .parse(ParseOptions::default().input(text))
+                .is_err())
         }
     }
 
@@ -303,6 +797,244 @@ mod tests {
         assert!(parse(input, formula).syntax_eq(&reference));
     }
 
+    #[test]
+    fn parses_numeric_literals_when_enabled() {
+        let options = ParseOptions::default().with_numeric_literals();
+        let formula = options.parse_formula("λf.f 42").unwrap().value;
+        assert!(matches!(
+            &formula,
+            Formula::Abs(a) if matches!(&a.formula, Formula::App(a) if matches!(&a.right, Formula::Num(n) if n.value == 42))
+        ));
+        assert!(matches!(
+            parse_formula("42").unwrap().value,
+            Formula::Var(v) if v.value == "42"
+        ));
+        assert!(options.parse_formula("4x").is_err());
+        assert!(options.parse_formula("λ4.4").is_err());
+    }
+
+    #[test]
+    fn rejects_too_large_numeric_literals() {
+        let options = ParseOptions::default().with_numeric_literals();
+        assert!(options.parse_formula("1000").is_ok());
+        for input in ["λf.f 1001", "1000000000000", "99999999999999999999999"] {
+            let errors = options.parse_formula(input).unwrap_err().0;
+            assert_eq!(1, errors.len(), "{errors:?}");
+            assert_eq!(NUMERAL_TOO_LARGE, errors[0].message);
+            assert_eq!(input.len(), errors[0].labels[0].location.end);
+            assert_eq!(
+                vec!["help: numeric literals can be at most 1000"],
+                errors[0].notes
+            );
+        }
+        let errors = options.parse_program("N -> (1001)\nN 1").unwrap_err().0;
+        assert_eq!(1, errors.len(), "{errors:?}");
+        assert_eq!(Span { start: 6, end: 10 }, errors[0].labels[0].location);
+    }
+
+    #[test]
+    fn parses_escaped_identifiers() {
+        for options in [
+            ParseOptions::default(),
+            ParseOptions::default().with_numeric_literals(),
+        ] {
+            let reference = parse("λx.x 42", formula);
+            let formula = options.parse_formula("λ`x`.x `42`").unwrap().value;
+            assert!(formula.syntax_eq(&reference));
+        }
+    }
+
+    #[test]
+    fn names_starting_with_digits_are_escaped_with_numeric_literals() {
+        let options = ParseOptions::default().with_numeric_literals();
+        let program = parse_program("0 -> (λf x.x)\n0").unwrap().value;
+        let name = &program.definitions[0].name;
+        assert_eq!("`0`", name.escaped(options));
+        assert_eq!("0", name.escaped(ParseOptions::default()));
+    }
+
+    #[test]
+    fn parses_list_and_string_literals() {
+        let formula = parse("[a, [], \"b c\" d]", formula);
+        let Formula::List(list) = &formula else {
+            panic!("{formula:?}")
+        };
+        assert_eq!(3, list.items.len());
+        assert!(matches!(&list.items[1], Formula::List(l) if l.items.is_empty()));
+        assert!(
+            matches!(&list.items[2], Formula::App(a) if matches!(&a.left, Formula::Str(s) if s.value == "b c"))
+        );
+        assert!(parse_formula("[a b,]").is_err());
+        assert!(parse_formula("\"a").is_err());
+    }
+
+    #[test]
+    fn rejects_characters_too_large_for_numerals() {
+        let errors = parse_formula("\"aé😀b\"").unwrap_err().0;
+        assert_eq!(1, errors.len(), "{errors:?}");
+        assert_eq!(CHARACTER_TOO_LARGE, errors[0].message);
+        assert_eq!(Span { start: 4, end: 8 }, errors[0].labels[0].location);
+    }
+
+    #[test]
+    fn records_list_encoding_of_literals() {
+        let options = ParseOptions::default().with_list_encoding(ListEncoding::Scott);
+        let formula = options.parse_formula("[\"\"]").unwrap().value;
+        assert!(matches!(
+            &formula,
+            Formula::List(l) if l.encoding == ListEncoding::Scott
+                && matches!(&l.items[0], Formula::Str(s) if s.encoding == ListEncoding::Scott)
+        ));
+    }
+
+    #[test]
+    fn parses_let_bindings() {
+        let parsed = parse("let x = A B in λy.x y", formula);
+        let Formula::Let(binding) = &parsed else {
+            panic!("{parsed:?}")
+        };
+        assert_eq!(LetStyle::Let, binding.style);
+        assert!(!binding.recursive);
+        assert_eq!("x", binding.name.value);
+        assert!(binding.value.syntax_eq(&parse("A B", formula)));
+        assert!(binding.body.syntax_eq(&parse("λy.x y", formula)));
+        assert_eq!(Span { start: 0, end: 22 }, binding.span);
+
+        let parsed = parse("let rec f = λn.f n in f", formula);
+        assert!(matches!(parsed, Formula::Let(binding) if binding.recursive));
+    }
+
+    #[test]
+    fn parses_where_bindings() {
+        let parsed = parse("f x where f = g where rec g = g", formula);
+        let Formula::Let(outer) = &parsed else {
+            panic!("{parsed:?}")
+        };
+        assert_eq!(LetStyle::Where, outer.style);
+        assert!(outer.recursive);
+        assert_eq!("g", outer.name.value);
+        assert!(matches!(&outer.body, Formula::Let(inner) if inner.name.value == "f"));
+        assert_eq!(Span { start: 0, end: 31 }, outer.span);
+    }
+
+    #[test]
+    fn keywords_are_only_names_when_escaped() {
+        for input in ["λin.in", "let = x in x", "let x = y", "x where", "rec"] {
+            assert!(parse_formula(input).is_err(), "{input}");
+        }
+        assert!(parse_formula("λ`in`.`in` letter within").is_ok());
+        let program = parse_program("let `let` = `in` in `let`").unwrap().value;
+        let displayed = program.to_string();
+        let reparsed = parse_program(&displayed).unwrap().value;
+        assert!(program.syntax_eq(&reparsed));
+    }
+
+    #[test]
+    fn parses_imports_between_definitions() {
+        let program = parse_program("import \"a.lc\"\nI -> (λx.x)\nimport \"b c.lc\"\nI")
+            .unwrap()
+            .value;
+        let paths: Vec<_> = program.imports.iter().map(|import| import.path).collect();
+        assert_eq!(vec!["a.lc", "b c.lc"], paths);
+        assert_eq!(1, program.definitions.len());
+        assert_eq!(Span { start: 0, end: 13 }, program.imports[0].span);
+    }
+
+    #[test]
+    fn parses_module_without_formula() {
+        let module = parse_module("import \"a.lc\"\nI -> (λx.x)\n# done\n")
+            .unwrap()
+            .value;
+        assert_eq!(1, module.imports.len());
+        assert_eq!(1, module.definitions.len());
+        assert!(parse_module("I -> (λx.x)\nI").is_err());
+    }
+
+    #[test]
+    fn variables_may_be_called_import() {
+        let reference = parse("(λimport.import) import", formula);
+        let program = parse_program("(λimport.import) import").unwrap().value;
+        assert!(program.imports.is_empty());
+        assert!(program.formula.syntax_eq(&reference));
+    }
+
+    #[test]
+    fn parses_input_without_items() {
+        for input in ["", "  ", "# c\n"] {
+            let module = parse_module(input).unwrap();
+            assert!(module.definitions.is_empty());
+            let diagnostics = parse_program(input).unwrap_err().0;
+            let messages: Vec<_> = diagnostics.iter().map(|d| &d.message).collect();
+            assert_eq!(vec![EXPECTED_FORMULA], messages);
+        }
+    }
+
+    #[test]
+    fn reports_every_error_of_a_program() {
+        let input = "I -> (λx.(x!))\nK -> (λx y.)\nB -> λx.x\nS -> (λx y z.x z (y z))\nS K K";
+        let diagnostics = parse_program(input).unwrap_err().0;
+        let locations: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.labels[0].location.start)
+            .collect();
+        let positions = [input.find('!'), input.find(")\nB"), input.find("λx.x\n")];
+        let expected: Vec<_> = positions.into_iter().flatten().collect();
+        assert_eq!(expected, locations);
+    }
+
+    #[test]
+    fn recovers_partial_program_with_error_nodes() {
+        let input = "I -> (λx.(x!))\nK -> (λx y.)\nB -> λx.x\nS -> (λx y z.x z (y z))\nS K K";
+        let recovered = ParseOptions::default().recover_program(input);
+        assert_eq!(3, recovered.diagnostics.0.len());
+        let program = recovered.value;
+        let names: Vec<_> = program.definitions.iter().map(|d| d.name.value).collect();
+        assert_eq!(vec!["I", "K", "B", "S"], names);
+        assert!(matches!(
+            &program.definitions[0].formula,
+            Formula::Abs(a) if matches!(a.formula, Formula::Error(_))
+        ));
+        assert!(matches!(program.definitions[1].formula, Formula::Error(_)));
+        assert!(matches!(program.definitions[2].formula, Formula::Error(_)));
+        assert!(program.formula.syntax_eq(&parse("S K K", formula)));
+    }
+
+    #[test]
+    fn recovers_from_unclosed_parenthesis_at_next_definition() {
+        let input = "I -> (λx.(x x)\n\n# K\nK -> (λx y.x)\nK I";
+        let recovered = ParseOptions::default().recover_program(input);
+        assert_eq!(1, recovered.diagnostics.0.len());
+        let program = recovered.value;
+        assert_eq!(2, program.definitions.len());
+        let Formula::Error(span) = program.definitions[0].formula else {
+            panic!("{:?}", program.definitions[0].formula)
+        };
+        assert_eq!(input.find("\n\n# K"), Some(span.end));
+        assert!(program.formula.syntax_eq(&parse("K I", formula)));
+    }
+
+    #[test]
+    fn recovers_from_unexpected_input_between_definitions() {
+        let module = ParseOptions::default().recover_module("I -> (λx.x)\n) (\nK -> (λx y.x)");
+        assert_eq!(1, module.diagnostics.0.len());
+        assert_eq!(2, module.value.definitions.len());
+
+        let program = ParseOptions::default().recover_program("I -> (λx.x)\n");
+        let diagnostic = &program.diagnostics.0[0];
+        assert_eq!("expected a formula", diagnostic.message);
+        assert!(matches!(program.value.formula, Formula::Error(_)));
+
+        for input in [
+            "λx y",
+            "import \"a.lc\nI -> (λx.x)\nI",
+            "I -> x\n)\nI",
+            ") ) I",
+        ] {
+            let program = ParseOptions::default().recover_program(input);
+            assert_eq!(1, program.diagnostics.0.len(), "{input}");
+        }
+    }
+
     #[test]
     fn errors_are_reported_at_correct_location() {
         let inputs = &[
@@ -318,18 +1050,17 @@ mod tests {
         let error_index = input.find('!').unwrap();
         let expected_span = Span {
             start: error_index,
-            end: error_index,
+            end: error_index + 1,
         };
 
         let diagnostics = parse_formula(input).unwrap_err().0;
 
         assert_eq!(1, diagnostics.len());
         let diagnostic = diagnostics.first().unwrap();
-        assert_eq!(1, diagnostic.labels.len());
         assert_eq!(expected_span, diagnostic.labels.first().unwrap().location);
     }
 
     fn parse<'a, O>(input: &'a str, mut parser: impl Parser<'a, O>) -> O {
-        parser.parse(Input::new(input)).unwrap()
+        parser.parse(ParseOptions::default().input(input)).unwrap()
     }
 }
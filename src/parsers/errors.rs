@@ -0,0 +1,231 @@
+use super::{is_identifier_continue, is_identifier_start, Error, LAMBDA};
+use crate::diagnostics::*;
+use crate::syntax::{Numeral, Span, KEYWORDS};
+use winnow::error::VerboseErrorKind;
+use winnow::stream::Location;
+
+// What was expected when a parser failed, added with `context` to the parser of the token
+// that was expected, so that the context is at the same location as the failure.
+pub(super) const EXPECTED_PARAMETER: &str = "expected a parameter name after `λ`";
+pub(super) const EXPECTED_DOT: &str = "expected `.` after lambda parameters";
+pub(super) const EXPECTED_FORMULA: &str = "expected a formula";
+pub(super) const EXPECTED_CLOSING_PARENTHESIS: &str = "expected `)`";
+pub(super) const EXPECTED_DEFINITION_FORMULA: &str = "expected `(` after `->`";
+pub(super) const EXPECTED_LIST_END: &str = "expected `,` or `]` after list item";
+pub(super) const EXPECTED_QUOTE: &str = "expected `\"` at the end of the string";
+pub(super) const EXPECTED_BACKTICK: &str = "expected `` ` `` at the end of the escaped name";
+pub(super) const EXPECTED_SYMBOL: &str = "expected a name after `:`";
+pub(super) const EXPECTED_BOUND_NAME: &str = "expected a name to bind";
+pub(super) const EXPECTED_EQUALS: &str = "expected `=` after the bound name";
+pub(super) const EXPECTED_IN: &str = "expected `in` after the value of `let`";
+
+pub(super) const NUMERAL_TOO_LARGE: &str = "numeric literal too large";
+pub(super) const CHARACTER_TOO_LARGE: &str = "character too large to encode";
+
+const UNCLOSED_PARENTHESIS: &str = "unclosed `(`";
+const LAMBDA_IN_IDENTIFIER: &str = "`λ` cannot appear inside an identifier";
+
+fn help(message: &str) -> Option<String> {
+    let help = match message {
+        EXPECTED_DOT => "an abstraction is written as `λx y.body`",
+        EXPECTED_DEFINITION_FORMULA => {
+            "the formula of a definition is parenthesized, as in `I -> (λx.x)`"
+        }
+        EXPECTED_IN => "a binding is written as `let x = value in body`",
+        LAMBDA_IN_IDENTIFIER => "put a space before `λ` to start an abstraction",
+        NUMERAL_TOO_LARGE => {
+            return Some(format!("numeric literals can be at most {}", Numeral::MAX))
+        }
+        CHARACTER_TOO_LARGE => {
+            return Some(format!(
+                "characters are encoded as the numerals of their code points, \
+                 which can be at most {}",
+                Numeral::MAX
+            ))
+        }
+        _ => return None,
+    };
+    Some(help.to_owned())
+}
+
+/// Describes the innermost failure of `error`, which is where parsing couldn't go on.
+pub(super) fn to_diagnostic(error: Error) -> Diagnostic {
+    let (input, _) = error
+        .errors
+        .first()
+        .expect("At least one error was expected");
+    let location = input.location();
+    let rest: &str = &input.input;
+    let found = token(rest);
+    let span = Span {
+        start: location,
+        end: location + found.len(),
+    };
+    let previous = input.state.text[..location].chars().next_back();
+    let context = error.errors.iter().find_map(|(input, kind)| match kind {
+        VerboseErrorKind::Context(context) if input.location() == location => Some(*context),
+        _ => None,
+    });
+
+    let diagnostic = if found.starts_with(LAMBDA) && previous.is_some_and(is_identifier_continue) {
+        Diagnostic::new(DiagnosticSeverity::Error, LAMBDA_IN_IDENTIFIER)
+            .with_label(Label::new(span))
+    } else if let Some(context) = context {
+        Diagnostic::new(DiagnosticSeverity::Error, context)
+            .with_label(Label::new(span).with_message(describe_found(found)))
+    } else if found.is_empty() {
+        Diagnostic::new(DiagnosticSeverity::Error, "unexpected end of input")
+            .with_label(Label::new(span))
+    } else {
+        Diagnostic::new(DiagnosticSeverity::Error, format!("unexpected `{found}`"))
+            .with_label(Label::new(span))
+    };
+    let diagnostic = match help(&diagnostic.message) {
+        Some(help) => diagnostic.with_help(help),
+        None => diagnostic,
+    };
+    match KEYWORDS.contains(&found) {
+        true => diagnostic.with_help(format!(
+            "`{found}` is a keyword, write `` `{found}` `` to use it as a name"
+        )),
+        false => diagnostic,
+    }
+}
+
+/// Adds the opening parenthesis to a diagnostic about a missing closing one, which is
+/// about an unclosed parenthesis if it couldn't be found after the error either.
+pub(super) fn with_opening_parenthesis(
+    mut diagnostic: Diagnostic,
+    opening: Span,
+    closed: bool,
+) -> Diagnostic {
+    if diagnostic.message != EXPECTED_CLOSING_PARENTHESIS {
+        return diagnostic;
+    }
+    if closed {
+        return diagnostic.with_label(Label::new(opening).with_message("to close this `(`"));
+    }
+    diagnostic.message = UNCLOSED_PARENTHESIS.into();
+    let found = diagnostic.labels[0].message.take().unwrap_or_default();
+    diagnostic.labels[0].message = Some(format!("expected `)`, {found}"));
+    diagnostic.with_label(Label::new(opening).with_message("opened here"))
+}
+
+pub(super) fn formula_before_item(formula: Span, item: Span) -> Diagnostic {
+    Diagnostic::new(
+        DiagnosticSeverity::Error,
+        "the formula of a program has to come after its definitions and imports",
+    )
+    .with_label(Label::new(formula).with_message("has to come last"))
+    .with_label(Label::new(item).with_message("but is followed by this"))
+}
+
+/// The word or character at the start of `rest`.
+fn token(rest: &str) -> &str {
+    let mut chars = rest.char_indices();
+    match chars.next() {
+        None => "",
+        Some((_, c)) if is_identifier_start(c) => {
+            let end = chars
+                .find(|(_, c)| !is_identifier_continue(*c))
+                .map_or(rest.len(), |(index, _)| index);
+            &rest[..end]
+        }
+        Some((_, c)) => &rest[..c.len_utf8()],
+    }
+}
+
+fn describe_found(found: &str) -> String {
+    match found {
+        "" => "found end of input".to_owned(),
+        found => format!("found `{found}`"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_program;
+
+    #[track_caller]
+    fn error(input: &str) -> Diagnostic {
+        let diagnostics = parse_program(input).unwrap_err().0;
+        assert_eq!(1, diagnostics.len(), "{diagnostics:?}");
+        diagnostics.into_iter().next().unwrap()
+    }
+
+    fn label(diagnostic: &Diagnostic, index: usize) -> (Span, Option<&str>) {
+        let label = &diagnostic.labels[index];
+        (label.location, label.message.as_deref())
+    }
+
+    #[test]
+    fn names_the_expected_token() {
+        let diagnostic = error("λx y z");
+        assert_eq!(EXPECTED_DOT, diagnostic.message);
+        assert_eq!(
+            (Span { start: 7, end: 7 }, Some("found end of input")),
+            label(&diagnostic, 0)
+        );
+        assert_eq!(
+            vec!["help: an abstraction is written as `λx y.body`"],
+            diagnostic.notes
+        );
+
+        let diagnostic = error("I -> λx.x\nI");
+        assert_eq!(EXPECTED_DEFINITION_FORMULA, diagnostic.message);
+        assert_eq!(
+            (Span { start: 5, end: 7 }, Some("found `λ`")),
+            label(&diagnostic, 0)
+        );
+        assert_eq!(1, diagnostic.notes.len());
+
+        assert_eq!(EXPECTED_IN, error("let x = y").message);
+        assert_eq!(EXPECTED_FORMULA, error("λx.").message);
+        assert_eq!(EXPECTED_QUOTE, error("\"abc").message);
+    }
+
+    #[test]
+    fn spans_the_unexpected_word() {
+        let diagnostic = error("A B!");
+        assert_eq!("unexpected `!`", diagnostic.message);
+        let diagnostic = error("λin.x");
+        assert_eq!(EXPECTED_PARAMETER, diagnostic.message);
+        assert_eq!(Span { start: 2, end: 4 }, diagnostic.labels[0].location);
+        assert!(diagnostic.notes[0].contains("`in` is a keyword"));
+    }
+
+    #[test]
+    fn reports_lambda_in_identifier() {
+        let diagnostic = error("λfooλ.x");
+        assert_eq!(LAMBDA_IN_IDENTIFIER, diagnostic.message);
+        assert_eq!(Span { start: 5, end: 7 }, diagnostic.labels[0].location);
+        assert_eq!(1, diagnostic.notes.len());
+    }
+
+    #[test]
+    fn points_to_opening_parenthesis() {
+        let diagnostic = error("(A B\nK -> (λx.x)\nK");
+        assert_eq!(UNCLOSED_PARENTHESIS, diagnostic.message);
+        assert_eq!(
+            (Span { start: 5, end: 6 }, Some("expected `)`, found `K`")),
+            label(&diagnostic, 0)
+        );
+        assert_eq!(
+            (Span { start: 0, end: 1 }, Some("opened here")),
+            label(&diagnostic, 1)
+        );
+
+        let diagnostic = error("(A!) B");
+        assert_eq!(EXPECTED_CLOSING_PARENTHESIS, diagnostic.message);
+        assert_eq!(Span { start: 0, end: 1 }, diagnostic.labels[1].location);
+    }
+
+    #[test]
+    fn reports_formula_before_definitions() {
+        let diagnostic = error("A B\nK -> (λx.x)\nK");
+        assert_eq!(2, diagnostic.labels.len());
+        assert_eq!(Span { start: 0, end: 3 }, diagnostic.labels[0].location);
+        assert_eq!(Span { start: 4, end: 16 }, diagnostic.labels[1].location);
+    }
+}
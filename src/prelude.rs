@@ -0,0 +1,119 @@
+/// Definitions of booleans, numerals, pairs, lists, `Maybe`, `Either` and fixpoint combinators
+/// that are encoded like [`Encode`](crate::evaluation::Encode) does.
+///
+/// Programs can use them after [`Sources::with_prelude`](crate::Sources::with_prelude),
+/// their own definitions take precedence.
+pub const PRELUDE: &str = include_str!("prelude.lc");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation::{
+        alpha_eq, free_variables, Definitions, Encode as _, EvaluationOptions, Outcome, Term,
+    };
+    use crate::{parse_module, ParseOptions};
+
+    #[track_caller]
+    fn assert_evaluates_to(formula: &str, expected: Term<'_>) {
+        assert_evaluates_to_with(ParseOptions::default(), formula, expected);
+    }
+
+    #[track_caller]
+    fn assert_evaluates_to_with(options: ParseOptions, formula: &str, expected: Term<'_>) {
+        let sources = options.load_text("<test>", formula, ".").with_prelude();
+        let program = sources.program().unwrap().value;
+        let mut evaluation = EvaluationOptions::default()
+            .with_max_steps(100_000)
+            .evaluate(program);
+        let normal_form = evaluation.by_ref().last().unwrap().term;
+        assert_eq!(Some(Outcome::NormalForm), evaluation.outcome());
+        assert!(
+            alpha_eq(&expected, &normal_form),
+            "{formula} ->> {normal_form}, expected {expected}"
+        );
+    }
+
+    #[test]
+    fn definitions_only_refer_to_each_other() {
+        let definitions: Definitions = parse_module(PRELUDE).unwrap().value.definitions.into();
+        for (name, term) in definitions.iter() {
+            for variable in free_variables(term) {
+                assert!(definitions.contains(&variable), "{variable} in {name}");
+            }
+        }
+    }
+
+    #[test]
+    fn booleans() {
+        assert_evaluates_to("not True", false.encode());
+        assert_evaluates_to("and True False", false.encode());
+        assert_evaluates_to("or False True", true.encode());
+        assert_evaluates_to("if False 1 2", 2.encode());
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_evaluates_to("incr 10", 11.encode());
+        assert_evaluates_to("decr 0", 0.encode());
+        assert_evaluates_to("add 2 3", 5.encode());
+        assert_evaluates_to("sub 7 3", 4.encode());
+        assert_evaluates_to("mult 3 4", 12.encode());
+        assert_evaluates_to("pow 2 3", 8.encode());
+        assert_evaluates_to("isZero 0", true.encode());
+        assert_evaluates_to("isEven 3", false.encode());
+        assert_evaluates_to("leq 3 2", false.encode());
+        assert_evaluates_to("eq 4 4", true.encode());
+    }
+
+    #[test]
+    fn pairs() {
+        assert_evaluates_to("Pair 1 2", (1, 2).encode());
+        assert_evaluates_to("snd (Pair 1 2)", 2.encode());
+    }
+
+    #[test]
+    fn lists() {
+        let list = "(Cons 1 (Cons 2 (Cons 4 Nil)))";
+        assert_evaluates_to(&format!("sum (map incr {list})"), 10.encode());
+        assert_evaluates_to(&format!("length (filter isEven {list})"), 2.encode());
+        assert_evaluates_to(&format!("head (tail {list})"), Some(2).encode());
+        assert_evaluates_to(&format!("sum (append {list} {list})"), 14.encode());
+        assert_evaluates_to("head Nil", None::<u64>.encode());
+        assert_evaluates_to("isNil Nil", true.encode());
+    }
+
+    #[test]
+    fn maybe() {
+        assert_evaluates_to("mapMaybe incr (Just 1)", Some(2).encode());
+        assert_evaluates_to("bindMaybe Nothing Just", None::<u64>.encode());
+        assert_evaluates_to("fromMaybe 3 Nothing", 3.encode());
+        assert_evaluates_to("maybe 0 incr (Just 1)", 2.encode());
+    }
+
+    #[test]
+    fn either() {
+        assert_evaluates_to("either incr decr (Left 1)", 2.encode());
+        assert_evaluates_to("either incr decr (Right 1)", 0.encode());
+    }
+
+    #[test]
+    fn fixpoints() {
+        let factorial = "(λf n.isZero n 1 (mult n (f (decr n))))";
+        assert_evaluates_to(&format!("Y {factorial} 3"), 6.encode());
+        assert_evaluates_to(&format!("Z {factorial} 3"), 6.encode());
+    }
+
+    #[test]
+    fn can_be_used_with_numeric_literals() {
+        let options = ParseOptions::default().with_numeric_literals();
+        assert_evaluates_to_with(options, "add 20 `2`", 22.encode());
+        assert_evaluates_to_with(options, "length (Cons 7 Nil)", 1.encode());
+        assert_evaluates_to_with(options, "map incr [1, 2]", vec![2, 3].encode());
+        assert_evaluates_to("length \"abc\"", 3.encode());
+    }
+
+    #[test]
+    fn definitions_of_the_program_take_precedence() {
+        assert_evaluates_to("True -> (λa b.b)\nTrue", false.encode());
+    }
+}
@@ -1,9 +1,13 @@
-use std::cmp::min;
+use crate::evaluation::ListEncoding;
+use crate::ParseOptions;
+use std::borrow::Cow;
+use std::cmp::{max, min};
 use std::fmt;
 use std::ops::Range;
 
 #[derive(Debug, Clone)]
 pub struct Program<'a> {
+    pub imports: Vec<Import<'a>>,
     pub definitions: Vec<NominalDefinition<'a>>,
     pub formula: Formula<'a>,
     pub span: Span,
@@ -11,12 +15,15 @@ pub struct Program<'a> {
 
 impl SyntaxEq for Program<'_> {
     fn syntax_eq(&self, other: &Self) -> bool {
-        self.definitions.syntax_eq(&other.definitions) && self.formula.syntax_eq(&other.formula)
+        self.imports.syntax_eq(&other.imports)
+            && self.definitions.syntax_eq(&other.definitions)
+            && self.formula.syntax_eq(&other.formula)
     }
 }
 
 impl fmt::Display for Program<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.imports.iter().try_for_each(|i| writeln!(f, "{i}"))?;
         self.definitions
             .iter()
             .try_for_each(|d| writeln!(f, "{d}"))?;
@@ -24,12 +31,52 @@ impl fmt::Display for Program<'_> {
     }
 }
 
+/// A file without a formula that is only there to be imported.
+#[derive(Debug, Clone)]
+pub struct Module<'a> {
+    pub imports: Vec<Import<'a>>,
+    pub definitions: Vec<NominalDefinition<'a>>,
+    pub span: Span,
+}
+
+impl SyntaxEq for Module<'_> {
+    fn syntax_eq(&self, other: &Self) -> bool {
+        self.imports.syntax_eq(&other.imports) && self.definitions.syntax_eq(&other.definitions)
+    }
+}
+
+/// `import "path.lc"` makes the definitions of another file available.
+/// The path is relative to the importing file.
+#[derive(Debug, Clone)]
+pub struct Import<'a> {
+    pub path: &'a str,
+    pub span: Span,
+}
+
+impl fmt::Display for Import<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "import \"{}\"", self.path)
+    }
+}
+
+impl SyntaxEq for Import<'_> {
+    fn syntax_eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Formula<'a> {
     Abs(Box<Abstraction<'a>>),
     App(Box<Application<'a>>),
     Var(Identifier<'a>),
     Sym(Symbol<'a>),
+    Num(Numeral),
+    List(List<'a>),
+    Str(Text<'a>),
+    Let(Box<Let<'a>>),
+    /// Input that couldn't be parsed, and where the parser continued after reporting it.
+    Error(Span),
 }
 
 impl<'a> Formula<'a> {
@@ -47,6 +94,11 @@ impl<'a> Formula<'a> {
             Formula::App(a) => &a.span,
             Formula::Var(v) => &v.span,
             Formula::Sym(v) => &v.span,
+            Formula::Num(n) => &n.span,
+            Formula::List(l) => &l.span,
+            Formula::Str(s) => &s.span,
+            Formula::Let(l) => &l.span,
+            Formula::Error(span) => span,
         }
     }
 }
@@ -58,6 +110,11 @@ impl<'a> fmt::Display for Formula<'a> {
             Formula::App(app) => write!(f, "{app}"),
             Formula::Var(var) => write!(f, "{var}"),
             Formula::Sym(var) => write!(f, "{var}"),
+            Formula::Num(num) => write!(f, "{num}"),
+            Formula::List(list) => write!(f, "{list}"),
+            Formula::Str(text) => write!(f, "{text}"),
+            Formula::Let(binding) => write!(f, "{binding}"),
+            Formula::Error(_) => f.write_str("<error>"),
         }
     }
 }
@@ -68,6 +125,12 @@ impl<'a> SyntaxEq for Formula<'a> {
             (Formula::Abs(l), Formula::Abs(r)) => l.syntax_eq(r),
             (Formula::App(l), Formula::App(r)) => l.syntax_eq(r),
             (Formula::Var(l), Formula::Var(r)) => l.syntax_eq(r),
+            (Formula::Sym(l), Formula::Sym(r)) => l.syntax_eq(r),
+            (Formula::Num(l), Formula::Num(r)) => l.syntax_eq(r),
+            (Formula::List(l), Formula::List(r)) => l.syntax_eq(r),
+            (Formula::Str(l), Formula::Str(r)) => l.syntax_eq(r),
+            (Formula::Let(l), Formula::Let(r)) => l.syntax_eq(r),
+            (Formula::Error(_), Formula::Error(_)) => true,
             _ => false,
         }
     }
@@ -111,6 +174,91 @@ impl<'a> SyntaxEq for Application<'a> {
     }
 }
 
+/// `let x = value in body` or `body where x = value`, which both stand for `(λx.body) value`.
+/// With `rec`, `x` can be used in `value` too, which is made possible by a fixpoint combinator.
+#[derive(Debug, Clone)]
+pub struct Let<'a> {
+    pub style: LetStyle,
+    pub recursive: bool,
+    pub name: Identifier<'a>,
+    pub value: Formula<'a>,
+    pub body: Formula<'a>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LetStyle {
+    /// `let x = value in body`
+    Let,
+    /// `body where x = value`
+    Where,
+}
+
+impl<'a> Let<'a> {
+    /// The formula that this binding stands for. All of its parts have the span of the binding.
+    pub fn desugar(self) -> Formula<'a> {
+        let span = self.span;
+        let abstraction = |variable, formula| {
+            Formula::abs(Abstraction {
+                variable,
+                formula,
+                span,
+            })
+        };
+        let application = |left, right| Formula::app(Application { left, right, span });
+        let value = if self.recursive {
+            // Z = λf.(λx.f (λv.x x v)) (λx.f (λv.x x v)), which also works with call-by-value
+            let var = |value| Identifier { value, span };
+            let half = abstraction(
+                var("x"),
+                application(
+                    Formula::Var(var("f")),
+                    abstraction(
+                        var("v"),
+                        application(
+                            application(Formula::Var(var("x")), Formula::Var(var("x"))),
+                            Formula::Var(var("v")),
+                        ),
+                    ),
+                ),
+            );
+            let z = abstraction(var("f"), application(half.clone(), half));
+            application(z, abstraction(self.name.clone(), self.value))
+        } else {
+            self.value
+        };
+        application(abstraction(self.name, self.body), value)
+    }
+}
+
+impl fmt::Display for Let<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rec = if self.recursive { "rec " } else { "" };
+        match self.style {
+            LetStyle::Let => write!(
+                f,
+                "(let {rec}{} = {} in {})",
+                self.name, self.value, self.body
+            ),
+            LetStyle::Where => write!(
+                f,
+                "({} where {rec}{} = {})",
+                self.body, self.name, self.value
+            ),
+        }
+    }
+}
+
+impl SyntaxEq for Let<'_> {
+    fn syntax_eq(&self, other: &Self) -> bool {
+        self.style == other.style
+            && self.recursive == other.recursive
+            && self.name.syntax_eq(&other.name)
+            && self.value.syntax_eq(&other.value)
+            && self.body.syntax_eq(&other.body)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct NominalDefinition<'a> {
     pub name: Identifier<'a>,
@@ -130,15 +278,34 @@ impl<'a> SyntaxEq for NominalDefinition<'a> {
     }
 }
 
+/// Words that are part of the syntax and can only be names when they're escaped, e.g. `` `in` ``.
+pub const KEYWORDS: &[&str] = &["let", "rec", "in", "where"];
+
 #[derive(Debug, Clone)]
 pub struct Identifier<'a> {
     pub value: &'a str,
     pub span: Span,
 }
 
+impl<'a> Identifier<'a> {
+    /// The name as it has to be written to be read with `options`. Names that can't be used
+    /// as they are (see [`KEYWORDS`]) are escaped, and with numeric literals so are names that
+    /// start with a digit, so that they aren't confused with [`Numeral`]s.
+    pub fn escaped(&self, options: ParseOptions) -> Cow<'a, str> {
+        let numeral =
+            options.numeric_literals() && self.value.starts_with(|c: char| c.is_ascii_digit());
+        if numeral || KEYWORDS.contains(&self.value) {
+            Cow::Owned(format!("`{}`", self.value))
+        } else {
+            Cow::Borrowed(self.value)
+        }
+    }
+}
+
+/// Like [`Identifier::escaped`] with the default [`ParseOptions`].
 impl<'a> fmt::Display for Identifier<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.value)
+        f.write_str(&self.escaped(ParseOptions::default()))
     }
 }
 
@@ -167,7 +334,84 @@ impl<'a> SyntaxEq for Symbol<'a> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// An integer literal that stands for a Church numeral,
+/// see [`ParseOptions::with_numeric_literals`](crate::ParseOptions::with_numeric_literals).
+#[derive(Debug, Clone)]
+pub struct Numeral {
+    pub value: u64,
+    pub span: Span,
+}
+
+impl Numeral {
+    /// The largest numeric literal.
+    ///
+    /// The Church numeral `n` nests `n` applications, and terms are evaluated, compared and
+    /// printed recursively, so larger numerals run out of stack. 1000 is far more than
+    /// examples need and leaves room for the larger terms that arithmetic on them builds.
+    pub const MAX: u64 = 1_000;
+}
+
+impl fmt::Display for Numeral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl SyntaxEq for Numeral {
+    fn syntax_eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+/// `[a, b, c]`, which stands for a list of the formulas in the given encoding.
+#[derive(Debug, Clone)]
+pub struct List<'a> {
+    pub items: Vec<Formula<'a>>,
+    pub encoding: ListEncoding,
+    pub span: Span,
+}
+
+impl fmt::Display for List<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[")?;
+        for (index, item) in self.items.iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{item}")?;
+        }
+        f.write_str("]")
+    }
+}
+
+impl SyntaxEq for List<'_> {
+    fn syntax_eq(&self, other: &Self) -> bool {
+        self.encoding == other.encoding && self.items.syntax_eq(&other.items)
+    }
+}
+
+/// `"text"`, which stands for a list of the code points of its characters as Church numerals.
+/// There are no escape sequences, so the text can't contain `"`.
+#[derive(Debug, Clone)]
+pub struct Text<'a> {
+    pub value: &'a str,
+    pub encoding: ListEncoding,
+    pub span: Span,
+}
+
+impl fmt::Display for Text<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{}\"", self.value)
+    }
+}
+
+impl SyntaxEq for Text<'_> {
+    fn syntax_eq(&self, other: &Self) -> bool {
+        self.encoding == other.encoding && self.value == other.value
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Span {
     pub start: usize,
     pub end: usize,
@@ -189,11 +433,102 @@ impl Span {
     pub(crate) fn containing(a: &Span, b: &Span) -> Span {
         Span {
             start: min(a.start, b.start),
-            end: min(a.end, b.end),
+            end: max(a.end, b.end),
         }
     }
 }
 
+/// Moves all spans by `offset`, which places a file's syntax after the files before it
+/// (see [`crate::Sources`]).
+pub(crate) trait Shift {
+    fn shift(&mut self, offset: usize);
+}
+
+impl<T: Shift> Shift for Vec<T> {
+    fn shift(&mut self, offset: usize) {
+        self.iter_mut().for_each(|item| item.shift(offset));
+    }
+}
+
+impl Shift for Span {
+    fn shift(&mut self, offset: usize) {
+        self.start += offset;
+        self.end += offset;
+    }
+}
+
+impl Shift for Program<'_> {
+    fn shift(&mut self, offset: usize) {
+        self.imports.shift(offset);
+        self.definitions.shift(offset);
+        self.formula.shift(offset);
+        self.span.shift(offset);
+    }
+}
+
+impl Shift for Module<'_> {
+    fn shift(&mut self, offset: usize) {
+        self.imports.shift(offset);
+        self.definitions.shift(offset);
+        self.span.shift(offset);
+    }
+}
+
+impl Shift for Import<'_> {
+    fn shift(&mut self, offset: usize) {
+        self.span.shift(offset);
+    }
+}
+
+impl Shift for NominalDefinition<'_> {
+    fn shift(&mut self, offset: usize) {
+        self.name.shift(offset);
+        self.formula.shift(offset);
+        self.span.shift(offset);
+    }
+}
+
+impl Shift for Formula<'_> {
+    fn shift(&mut self, offset: usize) {
+        match self {
+            Formula::Abs(abs) => {
+                abs.variable.shift(offset);
+                abs.formula.shift(offset);
+                abs.span.shift(offset);
+            }
+            Formula::App(app) => {
+                app.left.shift(offset);
+                app.right.shift(offset);
+                app.span.shift(offset);
+            }
+            Formula::Var(var) => var.shift(offset),
+            Formula::Sym(sym) => {
+                sym.ident.shift(offset);
+                sym.span.shift(offset);
+            }
+            Formula::Num(num) => num.span.shift(offset),
+            Formula::List(list) => {
+                list.items.shift(offset);
+                list.span.shift(offset);
+            }
+            Formula::Str(text) => text.span.shift(offset),
+            Formula::Let(binding) => {
+                binding.name.shift(offset);
+                binding.value.shift(offset);
+                binding.body.shift(offset);
+                binding.span.shift(offset);
+            }
+            Formula::Error(span) => span.shift(offset),
+        }
+    }
+}
+
+impl Shift for Identifier<'_> {
+    fn shift(&mut self, offset: usize) {
+        self.span.shift(offset);
+    }
+}
+
 pub trait SyntaxEq {
     fn syntax_eq(&self, other: &Self) -> bool;
 }
@@ -1,12 +1,28 @@
-use cauliflambda::evaluation::{evaluate, Value};
-use cauliflambda::parse_formula;
+use cauliflambda::evaluation::{
+    evaluate, evaluate_by_need, evaluate_graph, evaluate_nameless, evaluate_program, Decode as _,
+    Encode as _, EvaluationOptions, Graph, ListEncoding, Outcome, Program, StepKind, Term, Value,
+};
+use cauliflambda::{parse_formula, parse_program, FormatOptions, ParseOptions};
 
 #[test]
 fn factorial_of_four_can_be_computed() {
-    let formula = parse_formula(include_str!("factorial.lc")).unwrap();
+    let formula = numeric_literals()
+        .parse_formula(include_str!("factorial.lc"))
+        .unwrap();
     assert!(formula.diagnostics.0.is_empty());
-    let last_step = evaluate(formula.value).take(100_000).last().unwrap();
-    let normal_form = last_step.term;
+    let normal_form = normal_form(formula.value);
+
+    assert_eq!(Value::Integer(24), normal_form.decode().unwrap());
+}
+
+#[test]
+fn factorial_of_four_can_be_computed_after_formatting() {
+    let options = FormatOptions::default()
+        .with_parse_options(numeric_literals())
+        .with_width(40);
+    let formatted = options.format(include_str!("factorial.lc")).unwrap();
+    let formula = numeric_literals().parse_formula(&formatted).unwrap();
+    let normal_form = normal_form(formula.value);
 
     assert_eq!(Value::Integer(24), normal_form.decode().unwrap());
 }
@@ -15,8 +31,119 @@ fn factorial_of_four_can_be_computed() {
 fn three_is_not_even() {
     let formula = parse_formula(include_str!("is_even.lc")).unwrap();
     assert!(formula.diagnostics.0.is_empty());
-    let last_step = evaluate(formula.value).take(100_000).last().unwrap();
-    let normal_form = last_step.term;
+    let normal_form = normal_form(formula.value);
+
+    assert_eq!(Value::Bool(false), normal_form.decode().unwrap());
+}
+
+#[test]
+fn three_is_not_even_with_let_bindings() {
+    let formula = parse_formula(include_str!("is_even_let.lc")).unwrap();
+    assert!(formula.diagnostics.0.is_empty());
+    let normal_form = normal_form(formula.value);
+
+    assert_eq!(Value::Bool(false), normal_form.decode().unwrap());
+}
+
+#[test]
+fn three_is_filtered_out_by_is_even() {
+    let program = parse_program(include_str!("maybe.lc")).unwrap();
+    assert!(program.diagnostics.0.is_empty());
+    let normal_form = normal_form(program.value);
+
+    assert_eq!(None::<u64>.encode(), normal_form);
+}
+
+#[test]
+fn factorial_of_four_can_be_computed_by_need_in_fewer_steps() {
+    let formula = numeric_literals()
+        .parse_formula(include_str!("factorial.lc"))
+        .unwrap();
+    let by_name = evaluate(formula.value.clone()).take(100_000).count();
+    let by_need: Vec<_> = evaluate_by_need(formula.value).take(100_000).collect();
+    let normal_form = &by_need.last().unwrap().term;
+
+    assert_eq!(Value::Integer(24), normal_form.decode().unwrap());
+    assert!(by_need.len() < by_name, "{} < {by_name}", by_need.len());
+}
+
+#[test]
+fn three_is_not_even_without_alpha_conversions() {
+    let formula = parse_formula(include_str!("is_even.lc")).unwrap();
+    let steps: Vec<_> = evaluate_nameless(formula.value).take(100_000).collect();
+    let normal_form = Term::from(&steps.last().unwrap().1);
 
     assert_eq!(Value::Bool(false), normal_form.decode().unwrap());
+    assert!(steps.iter().all(|(kind, _)| *kind != StepKind::Alpha));
+}
+
+#[test]
+fn factorial_of_four_can_be_computed_with_sharing_in_fewer_steps() {
+    let formula = numeric_literals()
+        .parse_formula(include_str!("factorial.lc"))
+        .unwrap();
+    let by_name = evaluate(formula.value.clone()).take(100_000).count();
+    let mut graph = Graph::new(formula.value);
+    let shared = (0..100_000)
+        .take_while(|_| graph.reduce() != StepKind::Id)
+        .count();
+
+    assert_eq!(Value::Integer(24), graph.to_term().decode().unwrap());
+    assert!(shared < by_name, "{shared} < {by_name}");
+}
+
+#[test]
+fn thirteen_is_the_seventh_fibonacci_number() {
+    let program = parse_program(include_str!("fibonacci.lc")).unwrap();
+    let expected = 13u64.encode();
+
+    let normal_form = evaluate_program(program.value.clone()).last().unwrap().term;
+    assert_eq!(expected, normal_form);
+    let normal_form = evaluate_graph(program.value).last().unwrap().term;
+    assert_eq!(expected, normal_form);
+}
+
+#[test]
+fn factorial_of_four_can_be_defined_with_let_rec() {
+    let sources = numeric_literals()
+        .load_text(
+            "<test>",
+            "let rec fact = λn.isZero n 1 (mult n (fact (decr n))) in :n (fact 4)",
+            ".",
+        )
+        .with_prelude();
+    let normal_form = normal_form(sources.program().unwrap().value);
+
+    assert_eq!(Value::Integer(24), normal_form.decode().unwrap());
+}
+
+#[test]
+fn literals_are_decoded_with_hints() {
+    for encoding in ListEncoding::ALL {
+        let options = numeric_literals().with_list_encoding(encoding);
+        let formula = options
+            .parse_formula(":l :l :s [[\"λ\", \"\"], []]")
+            .unwrap();
+        let expected = Value::List(vec![
+            Value::List(vec![Value::String("λ".into()), Value::String("".into())]),
+            Value::List(vec![]),
+        ]);
+        assert_eq!(Some(expected), Value::decode(&formula.value.into()));
+    }
+    let formula = numeric_literals().parse_formula(":l :n [1, 2]").unwrap();
+    let value = Value::decode(&formula.value.into()).unwrap();
+    assert_eq!("[1, 2]", value.to_string());
+}
+
+fn normal_form<'a>(program: impl Into<Program<'a>>) -> Term<'a> {
+    let mut evaluation = EvaluationOptions::default()
+        .with_max_steps(100_000)
+        .evaluate(program);
+    let last_step = evaluation.by_ref().last().unwrap();
+    assert_eq!(Some(Outcome::NormalForm), evaluation.outcome());
+    last_step.term
+}
+
+fn numeric_literals() -> ParseOptions {
+    ParseOptions::default().with_numeric_literals()
 }